@@ -0,0 +1,145 @@
+//! Measure time and memory used to read line views.
+#![expect(clippy::print_stdout, reason = "benchmark results are printed")]
+
+use ::core::{
+    alloc::{GlobalAlloc, Layout},
+    fmt::Write as _,
+    hint::black_box,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+use ::std::{
+    alloc::System,
+    io::Cursor,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use ::line_viewer3::line_view::{LineView, provide::PathReadProvider};
+
+/// Allocator keeping track of allocated bytes.
+struct Counting;
+
+/// Bytes currently allocated.
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+/// Most bytes allocated at once.
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // SAFETY: forwarded with the same requirements
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        // SAFETY: forwarded with the same requirements
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+/// Allocator used by benchmarks.
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Amount of times each view is read.
+const RUNS: u32 = 10;
+
+/// Content with sections of lines sharing a command, and many lines without
+/// one. If equal is set the commands of all sections are equal.
+fn sections(sections: usize, lines: usize, equal: bool) -> String {
+    let mut content = String::from("#-title Benchmark\n");
+    for section in 0..sections {
+        let prefix = if equal { 0 } else { section };
+        _ = writeln!(content, "#-clean\n#-exe printf\n#-arg {prefix}: %s\\n");
+        _ = writeln!(content, "#-subtitle Section {section}");
+        for line in 0..lines {
+            if line % 4 == 0 {
+                content.push('\n');
+            } else {
+                _ = writeln!(content, "line {line} of section {section}");
+            }
+        }
+    }
+    content
+}
+
+/// Content importing the same fragment of lines many times, written to a
+/// temporary directory. Returns the path of the root file.
+fn shared_fragment(imports: usize, lines: usize) -> PathBuf {
+    let dir = ::std::env::temp_dir().join("line-viewer3-bench");
+    ::std::fs::create_dir_all(&dir).expect("benchmark directory should be writable");
+
+    let mut fragment = String::from("#-subtitle Fragment\n");
+    for line in 0..lines {
+        _ = writeln!(fragment, "fragment line {line}");
+    }
+    ::std::fs::write(dir.join("fragment.txtlv"), fragment)
+        .expect("benchmark fragment should be writable");
+
+    let mut root = String::from("#-title Benchmark\n#-exe printf\n#-arg %s\\n\n");
+    for _ in 0..imports {
+        root.push_str("#-lines fragment.txtlv\n");
+    }
+    let path = dir.join("root.txtlv");
+    ::std::fs::write(&path, root).expect("benchmark root should be writable");
+    path
+}
+
+/// Read content using read, printing time taken and memory used by the view.
+fn bench(name: &str, read: impl Fn() -> LineView) {
+    let mut elapsed = Duration::ZERO;
+    let mut retained = 0;
+    let mut peak = 0;
+    for _ in 0..RUNS {
+        let before = ALLOCATED.load(Ordering::Relaxed);
+        PEAK.store(before, Ordering::Relaxed);
+        let start = Instant::now();
+        let view = read();
+        elapsed += start.elapsed();
+        retained = ALLOCATED.load(Ordering::Relaxed) - before;
+        peak = PEAK.load(Ordering::Relaxed) - before;
+        drop(black_box(view));
+    }
+    println!(
+        "{name:<16} {:>10.2?} {:>10} KiB retained {:>10} KiB peak",
+        elapsed / RUNS,
+        retained / 1024,
+        peak / 1024,
+    );
+}
+
+/// Bench reading content from memory.
+fn bench_buf(name: &str, content: &str) {
+    bench(name, || {
+        LineView::read_buf(
+            Cursor::new(content.as_bytes().to_vec()),
+            PathReadProvider,
+            None,
+            Default::default(),
+        )
+        .expect("benchmark content should be readable")
+    });
+}
+
+/// Bench reading file at path.
+fn bench_path(name: &str, path: &Path) {
+    bench(name, || {
+        LineView::read_path(path.into(), PathReadProvider, None, Default::default())
+            .expect("benchmark file should be readable")
+    });
+}
+
+fn main() {
+    bench_buf("10x1000 lines", &sections(10, 1000, false));
+    bench_buf("1000x10 lines", &sections(1000, 10, false));
+    bench_buf("1000x10 equal", &sections(1000, 10, true));
+    bench_buf("100x1000 lines", &sections(100, 1000, false));
+    bench_path("200x2000 lines", &shared_fragment(200, 2000));
+}
@@ -13,7 +13,13 @@ use ::derive_more::{From, IsVariant};
 use ::katalog_lib::ThemeValueEnum;
 use ::patharg::{InputArg, OutputArg};
 
-use crate::line_view::{self, LineView};
+use crate::{
+    config::{self, Config},
+    history,
+    keymap::Keymap,
+    line_view::{self, LineView},
+    trust,
+};
 
 pub use Feature::{Disabled, Enabled};
 
@@ -72,6 +78,12 @@ pub enum Action {
     Daemon(Daemon),
     /// Print line-viewer file.
     Print(Print),
+    /// Print history of executed lines.
+    History(History),
+    /// Print path of configuration file, or the default configuration.
+    Config(ConfigCmd),
+    /// Print active key bindings.
+    Keys(Keys),
 }
 
 impl Default for Action {
@@ -193,6 +205,10 @@ pub struct Print {
     #[arg(long)]
     pub home: Option<PathBuf>,
 
+    /// Print the command executed by each line next to it.
+    #[arg(long)]
+    pub commands: bool,
+
     /// Where to print file.
     #[arg(default_value_t)]
     pub destination: OutputArg,
@@ -202,28 +218,35 @@ impl Print {
     /// Print line view.
     ///
     /// # Errors
+    /// If the configuration or trust store cannot be read.
     /// If the lines cannot be read/parsed.
     /// Or if they cannot be written.
     pub fn print(self) -> ::color_eyre::Result<()> {
         let Self {
             file,
             home,
+            commands,
             destination,
         } = self;
 
+        // home and trust decide what is read, so as when running the ui
+        // an unreadable configuration or trust store is an error
+        let config = Config::read()?;
+        let trust = trust::Store::read()?;
+        let home = home.or_else(|| config.home());
+        let policy = config.policy(trust, false);
         let view = match file {
             InputArg::Stdin => LineView::read_buf(
                 stdin().lock(),
-                line_view::provide::PathReadProvider,
+                line_view::provide::Decompress(line_view::provide::PathReadProvider),
                 home.as_deref(),
+                policy,
             ),
             InputArg::Path(path_buf) => LineView::read_path(
-                path_buf
-                    .to_str()
-                    .ok_or_else(|| eyre!("destination path {destination:?} is not valid utf-8"))?
-                    .into(),
-                line_view::provide::PathReadProvider,
+                path_buf.into(),
+                line_view::provide::Decompress(line_view::provide::PathReadProvider),
                 home.as_deref(),
+                policy,
             ),
         };
         let view = view.map_err(|err| eyre!(err))?;
@@ -233,7 +256,7 @@ impl Print {
             .map_err(|err| eyre!(err))?
             .map_right(BufWriter::new);
 
-        for line in &view {
+        for (idx, line) in view.iter().enumerate() {
             if line.is_title() {
                 destination.write_all(b"-- ").map_err(|err| eyre!(err))?;
             }
@@ -245,6 +268,17 @@ impl Print {
             destination
                 .write_all(line.text().as_bytes())
                 .map_err(|err| eyre!(err))?;
+            // titles show the command executing their whole section
+            if commands
+                && let Some(invocation) = if line.is_title() {
+                    line.cmd()
+                        .batch_invocation(&view.section(idx).cloned().collect::<Vec<_>>())
+                } else {
+                    line.invocation()
+                }
+            {
+                write!(destination, "\t$ {invocation}").map_err(|err| eyre!(err))?;
+            }
             destination.write_all(b"\n").map_err(|err| eyre!(err))?;
         }
 
@@ -252,37 +286,187 @@ impl Print {
     }
 }
 
+/// Print history of executed lines.
+#[derive(Debug, Clone, Args)]
+pub struct History {
+    /// Only print executions of lines from this file.
+    #[arg(long, short)]
+    pub source: Option<PathBuf>,
+
+    /// Only print executions that did not succeed.
+    #[arg(long)]
+    pub failed: bool,
+
+    /// Only print executions whose line or command matches regex.
+    #[arg(long, short)]
+    pub filter: Option<::regex::Regex>,
+
+    /// Max amount of executions to print, most recent are kept.
+    #[arg(long, short = 'n')]
+    pub limit: Option<usize>,
+
+    /// Print executions as json lines.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Where to print history.
+    #[arg(default_value_t)]
+    pub destination: OutputArg,
+}
+
+impl History {
+    /// Print history.
+    ///
+    /// # Errors
+    /// If history cannot be read.
+    /// Or if it cannot be written.
+    pub fn print(self) -> ::color_eyre::Result<()> {
+        let Self {
+            source,
+            failed,
+            filter,
+            limit,
+            json,
+            destination,
+        } = self;
+
+        let source = source.map(|source| source.canonicalize().unwrap_or(source));
+        let entries = history::read()?
+            .into_iter()
+            .filter(|entry| {
+                source.is_none()
+                    || entry.source.as_ref().map(history::StoredPath::to_path) == source
+            })
+            .filter(|entry| !failed || !entry.status.is_success())
+            .filter(|entry| {
+                filter.as_ref().is_none_or(|filter| {
+                    filter.is_match(&entry.text)
+                        || filter.is_match(&entry.command.program.display)
+                        || entry.command.args.iter().any(|arg| filter.is_match(arg))
+                })
+            })
+            .collect::<Vec<_>>();
+        let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+
+        let mut destination = destination
+            .create()
+            .map_err(|err| eyre!(err))?
+            .map_right(BufWriter::new);
+
+        for entry in entries.iter().skip(skip) {
+            if json {
+                ::serde_json::to_writer(&mut destination, entry)?;
+                writeln!(destination)?;
+                continue;
+            }
+
+            write!(
+                destination,
+                "{}\t{}\t",
+                ::humantime::format_rfc3339_seconds(entry.time()),
+                entry.status,
+            )?;
+            if let Some(source) = &entry.source {
+                write!(destination, "{}:{}\t", source.display, entry.line)?;
+            }
+            writeln!(destination, "{}", entry.command.command_line())?;
+        }
+
+        destination.flush()?;
+        Ok(())
+    }
+}
+
+/// Print path of configuration file, or the default configuration.
+#[derive(Debug, Clone, Args)]
+pub struct ConfigCmd {
+    /// Print a commented configuration with default values.
+    #[arg(long)]
+    pub print_default: bool,
+
+    /// Where to print.
+    #[arg(default_value_t)]
+    pub destination: OutputArg,
+}
+
+impl ConfigCmd {
+    /// Print configuration path or default configuration.
+    ///
+    /// # Errors
+    /// If no configuration directory could be determined.
+    /// Or if output cannot be written.
+    pub fn print(self) -> ::color_eyre::Result<()> {
+        let Self {
+            print_default,
+            destination,
+        } = self;
+        let content = if print_default {
+            String::from(config::TEMPLATE)
+        } else {
+            let path = config::path().ok_or_else(|| eyre!("no configuration directory found"))?;
+            format!("{}\n", path.display())
+        };
+        destination.write(content).map_err(|err| eyre!(err))
+    }
+}
+
+/// Print active key bindings.
+#[derive(Debug, Clone, Args)]
+pub struct Keys {
+    /// Where to print bindings.
+    #[arg(default_value_t)]
+    pub destination: OutputArg,
+}
+
+impl Keys {
+    /// Print key bindings, invalid and conflicting bindings are logged.
+    ///
+    /// # Errors
+    /// If configuration cannot be read.
+    /// Or if bindings cannot be written.
+    pub fn print(self) -> ::color_eyre::Result<()> {
+        let Self { destination } = self;
+        let (keymap, errors) = Keymap::new(&Config::read()?.keys);
+        for err in errors {
+            ::log::warn!("{err}");
+        }
+
+        let mut destination = destination
+            .create()
+            .map_err(|err| eyre!(err))?
+            .map_right(BufWriter::new);
+        for (sequence, action) in keymap.iter() {
+            writeln!(destination, "{sequence}\t{action}")?;
+        }
+        destination.flush()?;
+        Ok(())
+    }
+}
+
 /// Open line-viewer file.
-#[derive(Debug, Clone, Parser)]
+#[derive(Debug, Clone, Default, Parser)]
 #[command(author, version)]
 pub struct Open {
-    /// Theme to use for application.
-    #[arg(long, short, value_enum, default_value_t)]
-    pub theme: ThemeValueEnum,
+    /// Theme to use for application, overrides configuration.
+    #[arg(long, short, value_enum)]
+    pub theme: Option<ThemeValueEnum>,
 
     /// Use specified path as user home.
     #[arg(long)]
     pub home: Option<PathBuf>,
 
-    /// Should ipc be used.
-    #[arg(long, value_enum, default_value_t = Enabled)]
-    pub ipc: Feature,
+    /// Should ipc be used, overrides configuration.
+    #[arg(long, value_enum)]
+    pub ipc: Option<Feature>,
+
+    /// View file without executing any lines.
+    #[arg(long)]
+    pub no_exec: bool,
 
     /// File to open.
     pub file: Option<PathBuf>,
 }
 
-impl Default for Open {
-    fn default() -> Self {
-        Self {
-            theme: Default::default(),
-            home: None,
-            ipc: Enabled,
-            file: None,
-        }
-    }
-}
-
 /// Open application without any input subscribing to events from
 /// other invocations.
 #[derive(Debug, Clone, Args)]
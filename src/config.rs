@@ -0,0 +1,158 @@
+//! User configuration.
+
+use ::std::{collections::BTreeMap, io::ErrorKind, path::PathBuf};
+
+use ::clap::ValueEnum;
+use ::katalog_lib::ThemeValueEnum;
+use ::regex::Regex;
+use ::serde::{Deserialize, Deserializer};
+
+use crate::{keymap::Bindings, line_view::Policy, trust};
+
+/// Directory configuration is stored in.
+pub fn dir() -> Option<PathBuf> {
+    ::std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| ::std::env::home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")))
+}
+
+/// Path of configuration file.
+pub fn path() -> Option<PathBuf> {
+    dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Commented configuration template, parses to the default configuration.
+pub const TEMPLATE: &str = include_str!("../assets/config.toml");
+
+/// What to do when a line without a command is executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DefaultAction {
+    /// Nothing is done.
+    #[default]
+    None,
+    /// Line is copied to clipboard.
+    Copy,
+    /// Line is opened using the first matching opener.
+    Open,
+}
+
+/// Command used to open lines matching a pattern.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Opener {
+    /// Regex lines are matched against, none if invalid.
+    #[serde(deserialize_with = "pattern")]
+    pub pattern: Option<Regex>,
+    /// Program and arguments, the line is appended as the last argument.
+    pub command: Vec<String>,
+}
+
+/// Compile pattern of an opener once when configuration is read, invalid
+/// patterns are logged.
+fn pattern<'de, D>(deserializer: D) -> Result<Option<Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let pattern = String::deserialize(deserializer)?;
+    Ok(Regex::new(&pattern)
+        .map_err(|err| ::log::warn!("invalid opener pattern {pattern}\n{err}"))
+        .ok())
+}
+
+/// User configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Config {
+    /// Theme used if not given on command line.
+    pub theme: Option<String>,
+    /// Size of line text.
+    pub font_size: u16,
+    /// Home directory used if not given on command line.
+    pub home: Option<PathBuf>,
+    /// Use ipc if not set on command line.
+    pub ipc: bool,
+    /// What to do when a line without a command is executed.
+    pub default_action: DefaultAction,
+    /// Openers used by the open default action, first match is used.
+    pub opener: Vec<Opener>,
+    /// Programs commands may execute, if not set any program may be executed.
+    pub allowlist: Option<Vec<PathBuf>>,
+    /// Key bindings by action, replacing the default bindings of the action.
+    pub keys: BTreeMap<String, Bindings>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            font_size: 12,
+            home: None,
+            ipc: true,
+            default_action: DefaultAction::None,
+            opener: Vec::new(),
+            allowlist: None,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Read configuration file, the default configuration is used if it
+    /// does not exist.
+    ///
+    /// # Errors
+    /// If the configuration file cannot be read or parsed.
+    pub fn read() -> ::color_eyre::Result<Self> {
+        let Some(path) = path() else {
+            return Ok(Self::default());
+        };
+        match ::std::fs::read_to_string(&path) {
+            Ok(content) => Ok(::toml::from_str(&content)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Get configured theme, invalid themes are logged and ignored.
+    pub fn theme(&self) -> Option<ThemeValueEnum> {
+        let name = self.theme.as_deref()?;
+        ThemeValueEnum::from_str(name, true)
+            .map_err(|err| ::log::warn!("invalid theme {name} in config\n{err}"))
+            .ok()
+    }
+
+    /// Get configured home, with a leading `~/` replaced by the home
+    /// directory of user.
+    pub fn home(&self) -> Option<PathBuf> {
+        let home = self.home.as_ref()?;
+        match home.strip_prefix("~") {
+            Ok(rest) => ::std::env::home_dir().map(|dir| dir.join(rest)),
+            Err(..) => Some(home.clone()),
+        }
+    }
+
+    /// Get opener of first opener matching text, openers with invalid
+    /// patterns are skipped.
+    pub fn opener(&self, text: &str) -> Option<&Opener> {
+        self.opener.iter().find(|opener| {
+            opener
+                .pattern
+                .as_ref()
+                .is_some_and(|pattern| pattern.is_match(text))
+        })
+    }
+
+    /// Policy to load files with, commands of files not in trust store are
+    /// ignored.
+    pub fn policy(&self, trust: trust::Store, no_exec: bool) -> Policy {
+        let policy = Policy::default()
+            .no_exec(no_exec)
+            .trust(move |path| trust.is_trusted(path));
+        match &self.allowlist {
+            Some(allowlist) => policy.allowlist(allowlist.iter().cloned()),
+            None => policy,
+        }
+    }
+}
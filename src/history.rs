@@ -0,0 +1,333 @@
+//! Persistent history of executed lines.
+
+use ::core::time::Duration;
+use ::std::{
+    ffi::OsStr,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ::serde::{Deserialize, Serialize};
+
+use crate::line_view::{self, Invocation, Line, LineView, Policy};
+
+/// How an execution ended.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Process exited with a code.
+    Exited(i32),
+    /// Process was terminated by a signal.
+    Signaled,
+    /// Process was killed after running for too long.
+    TimedOut,
+    /// Process was killed by user.
+    Killed,
+    /// Process could not be spawned or waited on.
+    Failed(String),
+}
+
+impl Status {
+    /// Check if execution was successful.
+    pub const fn is_success(&self) -> bool {
+        matches!(self, Self::Exited(0))
+    }
+}
+
+impl ::core::fmt::Display for Status {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        match self {
+            Status::Exited(code) => write!(f, "exit {code}"),
+            Status::Signaled => f.write_str("signaled"),
+            Status::TimedOut => f.write_str("timed out"),
+            Status::Killed => f.write_str("killed"),
+            Status::Failed(err) => write!(f, "failed: {err}"),
+        }
+    }
+}
+
+/// Path as stored in history, paths are not required to be valid utf-8.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StoredPath {
+    /// Path for display, invalid bytes are replaced.
+    pub display: String,
+    /// Bytes of path if it is not valid utf-8.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bytes: Option<Vec<u8>>,
+}
+
+impl StoredPath {
+    /// Store path.
+    pub fn new(path: &Path) -> Self {
+        match path.to_str() {
+            Some(display) => Self {
+                display: display.to_owned(),
+                bytes: None,
+            },
+            None => Self {
+                display: path.to_string_lossy().into_owned(),
+                bytes: bytes_of(path.as_os_str()),
+            },
+        }
+    }
+
+    /// Get stored path.
+    pub fn to_path(&self) -> PathBuf {
+        self.bytes
+            .as_deref()
+            .and_then(os_str_of)
+            .map_or_else(|| PathBuf::from(&self.display), PathBuf::from)
+    }
+}
+
+/// Bytes of a string of the os.
+#[cfg(unix)]
+fn bytes_of(s: &OsStr) -> Option<Vec<u8>> {
+    use ::std::os::unix::ffi::OsStrExt;
+    Some(s.as_bytes().to_vec())
+}
+
+/// Bytes of a string of the os, only unix strings are stored as bytes.
+#[cfg(not(unix))]
+const fn bytes_of(_s: &OsStr) -> Option<Vec<u8>> {
+    None
+}
+
+/// String of the os from bytes.
+#[cfg(unix)]
+fn os_str_of(bytes: &[u8]) -> Option<&OsStr> {
+    use ::std::os::unix::ffi::OsStrExt;
+    Some(OsStr::from_bytes(bytes))
+}
+
+/// String of the os from bytes, only unix strings are stored as bytes.
+#[cfg(not(unix))]
+const fn os_str_of(_bytes: &[u8]) -> Option<&OsStr> {
+    None
+}
+
+/// Process that was spawned, as stored in history. Values of environment
+/// variables and content written to stdin are left out since they may hold
+/// secrets.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Command {
+    /// Program that was run.
+    pub program: StoredPath,
+    /// Arguments passed to program.
+    pub args: Vec<String>,
+    /// Names of environment variables set by the command, the `LINE_VIEW_*`
+    /// variables set for every command are not included.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    /// If the environment of the viewer was not inherited.
+    #[serde(default)]
+    pub env_clear: bool,
+    /// Working directory of program.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<StoredPath>,
+    /// If content was written to stdin.
+    #[serde(default)]
+    pub stdin: bool,
+    /// If stdout and stderr were captured.
+    #[serde(default)]
+    pub capture: bool,
+    /// If process was sandboxed.
+    #[serde(default)]
+    pub sandbox: bool,
+}
+
+impl Command {
+    /// Create a record of invocation.
+    pub fn new(invocation: &Invocation) -> Self {
+        Self {
+            program: StoredPath::new(&invocation.program),
+            args: invocation.args.clone(),
+            env: invocation
+                .env
+                .iter()
+                .map(|(key, _)| key)
+                .filter(|key| !key.starts_with("LINE_VIEW_"))
+                .cloned()
+                .collect(),
+            env_clear: invocation.env_clear,
+            cwd: invocation.cwd.as_deref().map(StoredPath::new),
+            stdin: invocation.stdin.is_some(),
+            capture: invocation.capture,
+            sandbox: invocation.sandbox.is_some(),
+        }
+    }
+
+    /// Check if command is the one resolved to invocation, disregarding
+    /// anything not stored.
+    pub fn is_of(&self, invocation: &Invocation) -> bool {
+        *self == Self::new(invocation)
+    }
+
+    /// Get command as a shell quoted command line.
+    pub fn command_line(&self) -> String {
+        ::shell_words::join(
+            ::core::iter::once(self.program.display.as_str())
+                .chain(self.args.iter().map(String::as_str)),
+        )
+    }
+}
+
+/// A single execution of a line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since unix epoch at which line was executed.
+    pub timestamp: u64,
+    /// File line was read from, if any.
+    pub source: Option<StoredPath>,
+    /// Line number of line in source.
+    pub line: usize,
+    /// Text of line.
+    pub text: String,
+    /// Process that was spawned.
+    pub command: Command,
+    /// Confirmation asked for before execution, asked again when rerun.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<String>,
+    /// How execution ended.
+    pub status: Status,
+    /// Process that was spawned, only kept in memory so entries read from
+    /// the history file are resolved again from their line to be rerun.
+    #[serde(skip)]
+    pub invocation: Option<Invocation>,
+}
+
+impl Entry {
+    /// Create a new entry for a line being executed, status is set to
+    /// failed until updated.
+    pub fn new<C>(line: &Line<C>, invocation: &Invocation, confirm: Option<&str>) -> Self {
+        Self {
+            timestamp: now(),
+            source: line.source().path().map(StoredPath::new),
+            line: line.line(),
+            text: line.text().to_owned(),
+            command: Command::new(invocation),
+            confirm: confirm.map(str::to_owned),
+            status: Status::Failed("did not exit".to_owned()),
+            invocation: Some(invocation.clone()),
+        }
+    }
+
+    /// Check if entry is an execution of line.
+    pub fn is_of<C>(&self, line: &Line<C>) -> bool {
+        self.line == line.line()
+            && match (&self.source, line.source().path()) {
+                (Some(source), Some(path)) => source.to_path() == path,
+                (None, None) => true,
+                _ => false,
+            }
+    }
+
+    /// Resolve the process to spawn for entry again from its line, used
+    /// for entries read from the history file. The line has to be unchanged
+    /// and still resolve to the stored command.
+    ///
+    /// # Errors
+    /// If the file of the line cannot be read or the line changed.
+    pub fn resolve(&self, home: Option<&Path>, policy: Policy) -> Result<Invocation, String> {
+        if let Some(invocation) = &self.invocation {
+            return Ok(invocation.clone());
+        }
+        let Some(source) = &self.source else {
+            return Err("line was not read from a file".to_owned());
+        };
+
+        let view = LineView::read_path(
+            source.to_path().into(),
+            line_view::provide::Decompress(line_view::provide::PathReadProvider),
+            home,
+            policy,
+        )
+        .map_err(|err| err.to_string())?;
+        view.iter()
+            .find(|line| self.is_of(line) && line.text() == self.text)
+            .and_then(|line| line.invocation())
+            .filter(|invocation| self.command.is_of(invocation))
+            .ok_or_else(|| {
+                format!(
+                    "line {} of {} changed or was run together with other lines",
+                    self.line, source.display
+                )
+            })
+    }
+
+    /// Copy entry for a new execution started now.
+    pub fn renew(&self) -> Self {
+        Self {
+            timestamp: now(),
+            status: Status::Failed("did not exit".to_owned()),
+            ..self.clone()
+        }
+    }
+
+    /// Get point in time of entry.
+    pub fn time(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(self.timestamp)
+    }
+}
+
+/// Seconds since unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Path of history file, `$XDG_STATE_HOME/line-viewer3/history.jsonl`.
+pub fn path() -> Option<PathBuf> {
+    ::std::env::var_os("XDG_STATE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| ::std::env::home_dir().map(|home| home.join(".local/state")))
+        .map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("history.jsonl"))
+}
+
+/// Append an entry to history file.
+///
+/// # Errors
+/// If the history file cannot be found or written to.
+pub fn append(entry: &Entry) -> ::color_eyre::Result<()> {
+    let path = path().ok_or_else(|| ::color_eyre::eyre::eyre!("could not find state directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut line = ::serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?
+        .write_all(&line)?;
+    Ok(())
+}
+
+/// Read all entries of history file, oldest first. Lines that cannot be
+/// parsed are skipped.
+///
+/// # Errors
+/// If the history file cannot be found or read.
+pub fn read() -> ::color_eyre::Result<Vec<Entry>> {
+    let path = path().ok_or_else(|| ::color_eyre::eyre::eyre!("could not find state directory"))?;
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ::std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        match ::serde_json::from_str(&line?) {
+            Ok(entry) => entries.push(entry),
+            Err(err) => ::log::warn!("skipping invalid history entry in {path:?}\n{err}"),
+        }
+    }
+    Ok(entries)
+}
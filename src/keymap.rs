@@ -0,0 +1,378 @@
+//! Mapping of key presses to actions.
+
+use ::core::{fmt::Display, str::FromStr};
+use ::std::collections::BTreeMap;
+
+use ::serde::Deserialize;
+
+/// Something a key binding may do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+    /// Focus next widget.
+    FocusNext,
+    /// Focus previous widget.
+    FocusPrev,
+    /// Unfocus, hide filter and dialogs.
+    Disengage,
+    /// Exit application.
+    Quit,
+    /// Close all sections.
+    CollapseAll,
+    /// Open all sections.
+    UncollapseAll,
+    /// Show or hide filter.
+    ToggleFilter,
+    /// Show or hide history.
+    ToggleHistory,
+    /// Select or deselect line at cursor, or hovered line if there is no
+    /// cursor.
+    SelectHovered,
+    /// Select all visible lines.
+    SelectAll,
+    /// Execute selected lines.
+    ExecSelected,
+    /// Scroll to first line.
+    ScrollTop,
+    /// Scroll to last line.
+    ScrollBottom,
+    /// Move cursor to next line.
+    CursorDown,
+    /// Move cursor to previous line.
+    CursorUp,
+    /// Move cursor to next line, extending selection to it.
+    ExtendDown,
+    /// Move cursor to previous line, extending selection to it.
+    ExtendUp,
+}
+
+impl Action {
+    /// All actions.
+    pub const ALL: [Self; 17] = [
+        Self::FocusNext,
+        Self::FocusPrev,
+        Self::Disengage,
+        Self::Quit,
+        Self::CollapseAll,
+        Self::UncollapseAll,
+        Self::ToggleFilter,
+        Self::ToggleHistory,
+        Self::SelectHovered,
+        Self::SelectAll,
+        Self::ExecSelected,
+        Self::ScrollTop,
+        Self::ScrollBottom,
+        Self::CursorDown,
+        Self::CursorUp,
+        Self::ExtendDown,
+        Self::ExtendUp,
+    ];
+
+    /// Name of action used in configuration.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::FocusNext => "focus-next",
+            Self::FocusPrev => "focus-prev",
+            Self::Disengage => "disengage",
+            Self::Quit => "quit",
+            Self::CollapseAll => "collapse-all",
+            Self::UncollapseAll => "uncollapse-all",
+            Self::ToggleFilter => "toggle-filter",
+            Self::ToggleHistory => "toggle-history",
+            Self::SelectHovered => "select-hovered",
+            Self::SelectAll => "select-all",
+            Self::ExecSelected => "exec-selected",
+            Self::ScrollTop => "scroll-top",
+            Self::ScrollBottom => "scroll-bottom",
+            Self::CursorDown => "cursor-down",
+            Self::CursorUp => "cursor-up",
+            Self::ExtendDown => "extend-down",
+            Self::ExtendUp => "extend-up",
+        }
+    }
+
+    /// Default key bindings of action.
+    const fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Self::FocusNext => &["tab"],
+            Self::FocusPrev => &["shift+tab"],
+            Self::Disengage => &["escape"],
+            Self::Quit => &["ctrl+q"],
+            Self::CollapseAll => &["t", "-"],
+            Self::UncollapseAll => &["shift+t", "+"],
+            Self::ToggleFilter => &["f"],
+            Self::ToggleHistory => &["h"],
+            Self::SelectHovered => &["space"],
+            Self::SelectAll => &["ctrl+a"],
+            Self::ExecSelected => &["enter"],
+            Self::ScrollTop => &["g g", "home"],
+            Self::ScrollBottom => &["shift+g", "end"],
+            Self::CursorDown => &["j", "arrowdown"],
+            Self::CursorUp => &["k", "arrowup"],
+            Self::ExtendDown => &["shift+j", "shift+arrowdown"],
+            Self::ExtendUp => &["shift+k", "shift+arrowup"],
+        }
+    }
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|action| action.name() == s)
+            .ok_or_else(|| format!("{s} is not an action"))
+    }
+}
+
+/// A key pressed together with modifiers.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Chord {
+    /// Control is held.
+    pub ctrl: bool,
+    /// Alt is held.
+    pub alt: bool,
+    /// Shift is held.
+    pub shift: bool,
+    /// Logo key is held.
+    pub logo: bool,
+    /// Pressed key without modifiers applied, named keys are lowercase.
+    pub key: String,
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        let Self {
+            ctrl,
+            alt,
+            shift,
+            logo,
+            key,
+        } = self;
+        for (held, name) in [
+            (ctrl, "ctrl"),
+            (alt, "alt"),
+            (shift, "shift"),
+            (logo, "logo"),
+        ] {
+            if *held {
+                write!(f, "{name}+")?;
+            }
+        }
+        f.write_str(key)
+    }
+}
+
+impl FromStr for Chord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // the plus key is written as a trailing plus
+        let (modifiers, key) = match s.strip_suffix('+') {
+            Some(rest) if rest.is_empty() || rest.ends_with('+') => {
+                (rest.strip_suffix('+').unwrap_or(rest), "+")
+            }
+            _ => s.rsplit_once('+').unwrap_or(("", s)),
+        };
+        if key.is_empty() {
+            return Err(format!("{s} has no key"));
+        }
+
+        let mut chord = Self {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            logo: false,
+            key: if key.chars().count() == 1 {
+                key.to_owned()
+            } else {
+                key.to_lowercase()
+            },
+        };
+        for modifier in modifiers.split('+').filter(|modifier| !modifier.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                "logo" | "super" | "meta" => chord.logo = true,
+                other => return Err(format!("{other} is not a modifier in {s}")),
+            }
+        }
+        Ok(chord)
+    }
+}
+
+/// Chords pressed one after another.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sequence(pub Vec<Chord>);
+
+impl Sequence {
+    /// Check if other is a strict prefix of sequence.
+    fn has_prefix(&self, other: &[Chord]) -> bool {
+        self.0.len() > other.len() && self.0.starts_with(other)
+    }
+}
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        for (idx, chord) in self.0.iter().enumerate() {
+            if idx != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{chord}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Sequence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chords = s
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<Vec<Chord>, _>>()?;
+        if chords.is_empty() {
+            return Err(String::from("empty key binding"));
+        }
+        Ok(Self(chords))
+    }
+}
+
+/// Result of looking up pressed chords in a keymap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    /// Chords are bound to an action.
+    Action(Action),
+    /// Chords are the start of at least one binding.
+    Prefix,
+    /// Chords are not bound.
+    Unbound,
+}
+
+/// Key bindings of an action in configuration.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Bindings {
+    /// A single binding.
+    One(String),
+    /// Any amount of bindings, none unbinds the action.
+    Many(Vec<String>),
+}
+
+impl Bindings {
+    /// Iterate bindings.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        match self {
+            Self::One(binding) => ::core::slice::from_ref(binding),
+            Self::Many(bindings) => bindings.as_slice(),
+        }
+        .iter()
+        .map(String::as_str)
+    }
+}
+
+/// Maps sequences of chords to actions.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    /// Action of each sequence.
+    bindings: BTreeMap<Sequence, Action>,
+}
+
+impl Keymap {
+    /// Create keymap from default bindings, with the bindings of configured
+    /// actions replaced. Bindings that cannot be parsed and conflicting
+    /// bindings are returned as errors.
+    ///
+    /// Configured bindings take precedence over default bindings when bound
+    /// to the same keys, and a binding that is the start of another binding
+    /// makes the other binding unreachable.
+    pub fn new(configured: &BTreeMap<String, Bindings>) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let mut configured_actions = BTreeMap::new();
+        for (name, bindings) in configured {
+            match name.parse::<Action>() {
+                Ok(action) => {
+                    configured_actions.insert(action, bindings.iter().collect::<Vec<_>>());
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+
+        let defaults = Action::ALL
+            .into_iter()
+            .filter(|action| !configured_actions.contains_key(action))
+            .flat_map(|action| {
+                action
+                    .defaults()
+                    .iter()
+                    .map(move |binding| (action, *binding))
+            });
+        let bindings = configured_actions
+            .iter()
+            .flat_map(|(action, bindings)| bindings.iter().map(|binding| (*action, *binding)))
+            .chain(defaults)
+            .collect::<Vec<_>>();
+
+        let mut keymap = Self::default();
+        for (action, binding) in bindings {
+            let sequence = match binding.parse::<Sequence>() {
+                Ok(sequence) => sequence,
+                Err(err) => {
+                    errors.push(format!("could not bind {action}, {err}"));
+                    continue;
+                }
+            };
+            match keymap.bindings.get(&sequence) {
+                Some(bound) if *bound != action => errors.push(format!(
+                    "{sequence} is bound to both {bound} and {action}, using {bound}"
+                )),
+                Some(..) => {}
+                None => {
+                    keymap.bindings.insert(sequence, action);
+                }
+            }
+        }
+
+        for (prefix, action) in &keymap.bindings {
+            for (sequence, shadowed) in &keymap.bindings {
+                if sequence.has_prefix(&prefix.0) {
+                    errors.push(format!(
+                        "{sequence} ({shadowed}) cannot be reached, {prefix} is bound to {action}"
+                    ));
+                }
+            }
+        }
+
+        (keymap, errors)
+    }
+
+    /// Look up chords pressed in order.
+    pub fn lookup(&self, chords: &[Chord]) -> Lookup {
+        if let Some(action) = self.bindings.get(&Sequence(chords.to_vec())) {
+            Lookup::Action(*action)
+        } else if self
+            .bindings
+            .keys()
+            .any(|sequence| sequence.has_prefix(chords))
+        {
+            Lookup::Prefix
+        } else {
+            Lookup::Unbound
+        }
+    }
+
+    /// Iterate bindings ordered by keys.
+    pub fn iter(&self) -> impl Iterator<Item = (&Sequence, Action)> {
+        self.bindings
+            .iter()
+            .map(|(sequence, action)| (sequence, *action))
+    }
+}
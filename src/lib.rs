@@ -1,5 +1,9 @@
 //! Application to view and execute commands using lines.
 
 pub mod cli;
+pub mod config;
+pub mod history;
+pub mod keymap;
 pub mod line_view;
+pub mod trust;
 pub mod ui;
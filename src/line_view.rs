@@ -11,14 +11,22 @@ mod error;
 mod import;
 mod line_view;
 mod path_ext;
+mod sandbox;
 
 pub mod provide;
 
 use ::std::path::Path;
 
-pub use self::{cmd::Cmd, directive::Directive, error::Error, import::Import, line_view::LineView};
+pub use self::{
+    cmd::{Cmd, CmdId, Invocation, Policy},
+    directive::Directive,
+    error::Error,
+    import::Import,
+    line_view::{LineView, line::Line},
+    sandbox::Sandbox,
+};
 
-type PathSet = rustc_hash::FxHashSet<std::sync::Arc<str>>;
+type PathSet = rustc_hash::FxHashSet<std::sync::Arc<Path>>;
 fn escape_path(
     line: &str,
     home: Option<&Path>,
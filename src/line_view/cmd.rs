@@ -1,11 +1,53 @@
-use ::std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+use ::core::{
+    cell::Cell,
+    fmt::Display,
+    num::{NonZeroU64, NonZeroUsize},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use ::std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet},
+    ffi::{OsStr, OsString},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Child, Stdio},
+    sync::Arc,
+};
 
-use crate::line_view::{Error, Result, line_view::line::Source as LineSource};
+use ::hashbrown::HashSet;
 
-#[derive(Debug, Clone, Default)]
+use crate::line_view::{Error, Line, Result, Sandbox, line_view::line::Context};
+
+/// How the text of a line is passed to a command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Input {
+    /// Line is appended as the last argument.
+    #[default]
+    Arg,
+    /// Line is written to stdin.
+    Stdin,
+    /// Line is not passed.
+    None,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Cmd {
     exe: Option<PathBuf>,
     arg: Vec<String>,
+    cwd: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    env_clear: bool,
+    input: Input,
+    timeout: Option<Duration>,
+    limit: Option<NonZeroUsize>,
+    capture: bool,
+    shell: Option<String>,
+    confirm: Option<String>,
+    batch: bool,
+    sandbox: Option<Sandbox>,
+    /// Command was cleared since its program is not allowed.
+    blocked: bool,
 }
 
 impl Cmd {
@@ -19,80 +61,622 @@ impl Cmd {
         self
     }
 
+    pub fn cwd(&mut self, cwd: PathBuf) -> &mut Self {
+        self.cwd = Some(cwd);
+        self
+    }
+
+    pub fn default_cwd(&mut self, cwd: impl FnOnce() -> PathBuf) -> &mut Self {
+        if self.cwd.is_none() {
+            self.cwd = Some(cwd());
+        }
+        self
+    }
+
+    pub fn env(&mut self, key: String, value: String) -> &mut Self {
+        self.env.push((key, value));
+        self
+    }
+
+    pub fn env_clear(&mut self) -> &mut Self {
+        self.env.clear();
+        self.env_clear = true;
+        self
+    }
+
+    pub const fn input(&mut self, input: Input) -> &mut Self {
+        self.input = input;
+        self
+    }
+
+    pub const fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub const fn limit(&mut self, limit: NonZeroUsize) -> &mut Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub const fn capture(&mut self) -> &mut Self {
+        self.capture = true;
+        self
+    }
+
+    /// Run script using a POSIX shell, which is given the script using
+    /// `-c` followed by `$0` and the positional parameters. Shells with
+    /// other conventions, such as fish or PowerShell, are not supported.
+    pub fn shell(&mut self, script: String) -> &mut Self {
+        self.shell = Some(script);
+        self
+    }
+
+    pub fn confirm(&mut self, message: String) -> &mut Self {
+        self.confirm = Some(message);
+        self
+    }
+
+    pub fn sandbox(&mut self, ro: Vec<PathBuf>, rw: Vec<PathBuf>, no_net: bool) -> &mut Self {
+        self.sandbox.get_or_insert_default().extend(ro, rw, no_net);
+        self
+    }
+
+    pub const fn batch(&mut self) -> &mut Self {
+        self.batch = true;
+        self
+    }
+
+    pub const fn is_batch(&self) -> bool {
+        self.batch
+    }
+
+    pub fn get_confirm(&self) -> Option<&str> {
+        self.confirm.as_deref()
+    }
+
+    /// Program ran by command, for shell scripts this is either the exe
+    /// if set, `$SHELL` or `/bin/sh`, which is expected to be POSIX compatible.
+    pub fn program(&self) -> Option<Cow<'_, Path>> {
+        if self.shell.is_some() {
+            Some(match &self.exe {
+                Some(exe) => Cow::Borrowed(exe.as_path()),
+                None => ::std::env::var_os("SHELL")
+                    .filter(|shell| !shell.is_empty())
+                    .map_or_else(
+                        || Cow::Borrowed(Path::new("/bin/sh")),
+                        |shell| Cow::Owned(PathBuf::from(shell)),
+                    ),
+            })
+        } else {
+            self.exe.as_deref().map(Cow::Borrowed)
+        }
+    }
+
+    pub const fn get_timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub const fn get_limit(&self) -> Option<NonZeroUsize> {
+        self.limit
+    }
+
+    pub const fn is_captured(&self) -> bool {
+        self.capture
+    }
+
+    /// Check if command was cleared by allowlist.
+    pub const fn is_blocked(&self) -> bool {
+        self.blocked
+    }
+
     pub const fn is_empty(&self) -> bool {
-        self.exe.is_none()
+        self.exe.is_none() && self.shell.is_none()
     }
 
-    pub fn execute(
-        &self,
-        line_nr: usize,
-        line_src: LineSource,
-        params: impl IntoIterator<Item = impl Into<String>>,
-    ) -> Result {
-        let Some(exe) = &self.exe else { return Ok(()) };
+    /// Resolve the invocation used to execute line with this command.
+    pub fn invocation<C>(&self, line: &Line<C>) -> Option<Invocation> {
+        self.batch_invocation(::core::slice::from_ref(line))
+    }
+
+    /// Resolve the invocation used to execute lines with this command in a
+    /// single process. Context other than the text is taken from the first line.
+    ///
+    /// Arguments using a line placeholder are repeated once per line, and
+    /// for batch scripts the texts are the only positional parameters.
+    pub fn batch_invocation<C>(&self, lines: &[Line<C>]) -> Option<Invocation> {
+        let program = self.program()?.into_owned();
+        let line = lines.first()?;
+
+        let texts = lines.iter().map(Line::text).collect::<Vec<_>>();
+        let text = texts.join("\n");
+        let line_nr = line.line().to_string();
+        // arguments are text, paths that are not valid utf-8 are passed
+        // as is through the environment only
+        let src_path = line.source().path().unwrap_or(Path::new(""));
+        let src_text = src_path.to_string_lossy();
+        let src = src_text.as_ref();
+        let Context { dir, section, tags } = line.context();
+        let dir_text = dir.to_string_lossy();
+        let dir_text = dir_text.as_ref();
+        let uses_text = Cell::new(false);
+        let placeholder = |text| {
+            let uses_text = &uses_text;
+            let line_nr = &line_nr;
+            move |name: &str| match name {
+                "" | "line" => {
+                    uses_text.set(true);
+                    Some(text)
+                }
+                "nr" => Some(line_nr.as_str()),
+                "src" => Some(src),
+                "dir" => Some(dir_text),
+                _ => None,
+            }
+        };
 
-        let args = self
-            .arg
+        let mut has_placeholder = false;
+        let mut args = Vec::new();
+        for arg in &self.arg {
+            uses_text.set(false);
+            let Some(expanded) = expand_placeholders(arg, placeholder(texts[0])) else {
+                args.push(arg.clone());
+                continue;
+            };
+            has_placeholder = true;
+            args.push(expanded);
+            if uses_text.get() {
+                args.extend(
+                    texts[1..]
+                        .iter()
+                        .filter_map(|text| expand_placeholders(arg, placeholder(text))),
+                );
+            }
+        }
+
+        if let Some(script) = &self.shell {
+            // line content is only ever passed as positional parameters
+            // and never as a part of the script
+            let params = if self.batch {
+                texts.iter().map(|text| (*text).to_owned()).collect()
+            } else {
+                vec![text.clone(), line_nr.clone(), src.to_owned()]
+            };
+            args.splice(
+                0..0,
+                ["-c".to_owned(), script.clone(), "line-viewer".to_owned()]
+                    .into_iter()
+                    .chain(params),
+            );
+        } else if !has_placeholder && self.input == Input::Arg {
+            // placeholders take the place of the implicit trailing argument
+            args.extend(texts.iter().map(|text| (*text).to_owned()));
+        }
+
+        let (title, root) = line.view().map_or(("", Path::new("")), |view| {
+            (
+                view.title.as_ref(),
+                view.root.path().unwrap_or(Path::new("")),
+            )
+        });
+        let env = self
+            .env
             .iter()
-            .map(String::from)
-            .chain(params.into_iter().map(|param| param.into()))
-            .collect::<Vec<String>>();
+            .map(|(key, value)| (key.clone(), OsString::from(value)))
+            .chain(
+                [
+                    ("LINE_VIEW_LINE", OsStr::new(&text)),
+                    ("LINE_VIEW_LINE_NR", OsStr::new(&line_nr)),
+                    ("LINE_VIEW_LINE_SRC", src_path.as_os_str()),
+                    ("LINE_VIEW_SRC_KIND", OsStr::new(line.source().kind())),
+                    ("LINE_VIEW_TITLE", OsStr::new(title)),
+                    ("LINE_VIEW_SECTION", OsStr::new(&section.join(" / "))),
+                    ("LINE_VIEW_ROOT", root.as_os_str()),
+                    ("LINE_VIEW_DIR", dir.as_os_str()),
+                    ("LINE_VIEW_INDEX", OsStr::new(&line.index().to_string())),
+                    ("LINE_VIEW_TAGS", OsStr::new(&tags.join(" "))),
+                ]
+                .map(|(key, value)| (key.to_owned(), value.to_owned())),
+            )
+            .collect();
 
-        ::std::process::Command::new(exe)
-            .env("LINE_VIEW_LINE_NR", line_nr.to_string())
-            .env("LINE_VIEW_LINE_SRC", line_src.to_string())
+        Some(Invocation {
+            program,
+            args,
+            env,
+            env_clear: self.env_clear,
+            cwd: self.cwd.clone(),
+            stdin: (self.input == Input::Stdin).then_some(text),
+            capture: self.capture,
+            sandbox: self.sandbox.clone(),
+        })
+    }
+}
+
+/// A fully resolved command ready to be spawned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Invocation {
+    /// Program to run.
+    pub program: PathBuf,
+    /// Arguments passed to program.
+    pub args: Vec<String>,
+    /// Environment variables set for program.
+    pub env: Vec<(String, OsString)>,
+    /// If the environment of the viewer is not inherited.
+    pub env_clear: bool,
+    /// Working directory of program.
+    pub cwd: Option<PathBuf>,
+    /// Content written to stdin.
+    pub stdin: Option<String>,
+    /// If stdout and stderr are captured.
+    pub capture: bool,
+    /// Restrictions applied to process.
+    pub sandbox: Option<Sandbox>,
+}
+
+impl Invocation {
+    /// Spawn a process for invocation.
+    pub fn spawn(self) -> Result<Child> {
+        let Self {
+            program,
+            args,
+            env,
+            env_clear,
+            cwd,
+            stdin,
+            capture,
+            sandbox,
+        } = self;
+        let mut command = ::std::process::Command::new(&program);
+
+        if let Some(sandbox) = &sandbox {
+            sandbox.apply(&mut command, cwd.as_deref())?;
+        }
+
+        if env_clear {
+            command.env_clear();
+        }
+
+        if let Some(cwd) = &cwd {
+            command.current_dir(cwd);
+        }
+
+        if stdin.is_some() {
+            command.stdin(Stdio::piped());
+        }
+
+        if capture {
+            command.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        let mut child = command
+            .envs(env)
             .args(&args)
             .spawn()
             .map_err(|err| Error::Spawn {
                 err,
-                program: exe.display().to_string(),
+                program: program.display().to_string(),
                 args,
             })?;
+
+        // written on a new thread since the process may not read all of it,
+        // in which case writing would block until it exits
+        if let Some(text) = stdin
+            && let Some(mut pipe) = child.stdin.take()
+        {
+            ::std::thread::spawn(move || match pipe.write_all(text.as_bytes()) {
+                Err(err) if err.kind() != ::std::io::ErrorKind::BrokenPipe => {
+                    ::log::warn!("could not write stdin of {program:?}\n{err}");
+                }
+                _ => {}
+            });
+        }
+
+        Ok(child)
+    }
+
+    /// Get invocation as a shell quoted command line.
+    pub fn command_line(&self) -> String {
+        ::shell_words::join(
+            ::core::iter::once(self.program.to_string_lossy().as_ref())
+                .chain(self.args.iter().map(String::as_str)),
+        )
+    }
+}
+
+/// Displayed as a shell command, `LINE_VIEW_*` variables are omitted.
+impl Display for Invocation {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        use ::shell_words::quote;
+
+        if let Some(cwd) = &self.cwd {
+            write!(f, "cd {} && ", quote(&cwd.to_string_lossy()))?;
+        }
+
+        let mut env = self
+            .env
+            .iter()
+            .filter(|(key, _)| !key.starts_with("LINE_VIEW_"))
+            .peekable();
+        if self.env_clear || env.peek().is_some() {
+            f.write_str(if self.env_clear { "env -i " } else { "env " })?;
+            for (key, value) in env {
+                write!(
+                    f,
+                    "{} ",
+                    quote(&format!("{key}={}", value.to_string_lossy()))
+                )?;
+            }
+        }
+
+        f.write_str(&self.command_line())?;
+
+        if let Some(stdin) = &self.stdin {
+            write!(f, " <<< {}", quote(stdin))?;
+        }
+
         Ok(())
     }
 }
 
+/// Expand `{name}` placeholders in arg, unknown names are left as is.
+/// Returns `None` if no placeholder was expanded.
+fn expand_placeholders<'a>(arg: &str, lookup: impl Fn(&str) -> Option<&'a str>) -> Option<String> {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut has_placeholder = false;
+    let mut rest = arg;
+
+    while let Some(start) = rest.find('{') {
+        let (before, after) = rest.split_at(start);
+        expanded.push_str(before);
+
+        if let Some(end) = after.find('}')
+            && let Some(value) = lookup(&after[1..end])
+        {
+            expanded.push_str(value);
+            has_placeholder = true;
+            rest = &after[end + 1..];
+        } else {
+            expanded.push('{');
+            rest = &after[1..];
+        }
+    }
+    expanded.push_str(rest);
+
+    has_placeholder.then_some(expanded)
+}
+
+/// Check used to decide if commands defined by a file may be used.
+pub type Trust = Arc<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Restrictions on which commands may be defined and executed.
+#[derive(Clone, Default)]
+pub struct Policy {
+    /// No commands may be defined.
+    no_exec: bool,
+    /// Files whose commands may be used, in-memory content is always trusted.
+    trust: Option<Trust>,
+    /// Programs commands may execute.
+    allowlist: Option<Arc<[PathBuf]>>,
+}
+
+impl Policy {
+    pub const fn no_exec(mut self, no_exec: bool) -> Self {
+        self.no_exec = no_exec;
+        self
+    }
+
+    pub fn trust(mut self, trust: impl Fn(&Path) -> bool + Send + Sync + 'static) -> Self {
+        self.trust = Some(Arc::new(trust));
+        self
+    }
+
+    /// Set programs that may be executed, names are resolved using `PATH`
+    /// of the viewer.
+    pub fn allowlist(mut self, allowlist: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.allowlist = Some(
+            allowlist
+                .into_iter()
+                .map(|program| {
+                    find_program(&program, None)
+                        .map_or(program, |found| found.canonicalize().unwrap_or(found))
+                })
+                .collect(),
+        );
+        self
+    }
+
+    pub const fn is_no_exec(&self) -> bool {
+        self.no_exec
+    }
+
+    /// Check if program may be executed.
+    pub fn allows(&self, program: &Path) -> bool {
+        self.resolve(program, None).is_some()
+    }
+
+    /// Check if invocation may be executed, see [Directory::apply_allowlist].
+    pub fn allows_invocation(&self, invocation: &Invocation) -> bool {
+        self.allowlist.is_none()
+            || (self.allows(&invocation.program)
+                && !invocation.env.iter().any(|(key, _)| is_loader_var(key)))
+    }
+
+    /// Resolve program to the file that is executed if it may be executed.
+    /// Without an allowlist program is returned as is.
+    fn resolve(&self, program: &Path, cwd: Option<&Path>) -> Option<PathBuf> {
+        let Some(allowlist) = &self.allowlist else {
+            return Some(program.to_path_buf());
+        };
+        let found = find_program(program, cwd)?;
+        let canonical = found.canonicalize().ok()?;
+        allowlist.contains(&canonical).then_some(found)
+    }
+}
+
+/// Find file executed for program, names without a separator are searched
+/// for in `PATH` of the viewer and relative paths are relative to cwd.
+fn find_program(program: &Path, cwd: Option<&Path>) -> Option<PathBuf> {
+    if program.parent() == Some(Path::new("")) {
+        ::std::env::split_paths(&::std::env::var_os("PATH")?)
+            .map(|dir| dir.join(program))
+            .find(|path| path.is_file())
+    } else {
+        let path = match cwd {
+            Some(cwd) => cwd.join(program),
+            None => program.to_path_buf(),
+        };
+        path.is_file().then_some(path)
+    }
+}
+
+/// Check if environment variable decides which files are loaded when
+/// executing a program.
+fn is_loader_var(key: &str) -> bool {
+    key == "PATH" || key.starts_with("LD_")
+}
+
+impl ::core::fmt::Debug for Policy {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Policy")
+            .field("no_exec", &self.no_exec)
+            .field("trust", &self.trust.is_some())
+            .field("allowlist", &self.allowlist)
+            .finish()
+    }
+}
+
+/// Index of a command in a directory, 32 bits since every line has one.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub struct Handle(usize, usize);
+pub struct Handle(u32);
+
+impl Handle {
+    const fn idx(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Identity of a command, unique for every handle of every directory.
+/// Equal commands of separate handles share an allocation but are still
+/// batched and limited separately.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CmdId(NonZeroU64);
+
+/// Amount of command identities created.
+static CMD_IDS: AtomicU64 = AtomicU64::new(0);
 
 #[derive(Debug, Clone)]
 pub struct Directory<T> {
-    contents: Vec<BTreeMap<usize, T>>,
+    /// Identity of command of each handle.
+    ids: Vec<CmdId>,
+    contents: Vec<T>,
+    /// Command of lines without one.
+    none: T,
+    policy: Policy,
+    trusted: BTreeMap<Arc<Path>, bool>,
 }
 
 impl Directory<Cmd> {
-    pub const fn new() -> Self {
+    pub fn new() -> Self {
+        Self::with_policy(Policy::default())
+    }
+
+    pub fn with_policy(policy: Policy) -> Self {
         Self {
+            ids: Vec::new(),
             contents: Vec::new(),
+            none: Cmd::default(),
+            policy,
+            trusted: BTreeMap::new(),
+        }
+    }
+
+    /// Check if commands may be defined by source, the trust of each
+    /// source is only checked once.
+    pub fn may_define(&mut self, source: Option<&Arc<Path>>) -> bool {
+        if self.policy.no_exec {
+            return false;
         }
+        let (Some(trust), Some(source)) = (&self.policy.trust, source) else {
+            return true;
+        };
+        *self
+            .trusted
+            .entry(Arc::clone(source))
+            .or_insert_with(|| trust(source))
     }
 
+    /// Sources whose commands were not defined since they are untrusted.
+    pub fn untrusted(&self) -> impl Iterator<Item = &Arc<Path>> {
+        self.trusted
+            .iter()
+            .filter(|(_, is_trusted)| !**is_trusted)
+            .map(|(source, _)| source)
+    }
+
+    /// Clear commands executing programs not on the allowlist, returning
+    /// the programs that were blocked.
+    ///
+    /// Allowed programs are replaced by the file that was checked, and
+    /// commands setting `PATH` or `LD_*` variables are blocked since they
+    /// change what is loaded. Scripts of allowed shells are not checked.
+    pub fn apply_allowlist(&mut self) -> BTreeSet<PathBuf> {
+        let mut blocked = BTreeSet::new();
+        if self.policy.allowlist.is_none() {
+            return blocked;
+        }
+        for cmd in &mut self.contents {
+            let Some(program) = cmd.program().map(Cow::into_owned) else {
+                continue;
+            };
+            match self.policy.resolve(&program, cmd.cwd.as_deref()) {
+                Some(resolved) if !cmd.env.iter().any(|(key, _)| is_loader_var(key)) => {
+                    cmd.exe = Some(resolved);
+                }
+                _ => {
+                    blocked.insert(program);
+                    *cmd = Cmd {
+                        blocked: true,
+                        ..Cmd::default()
+                    };
+                }
+            }
+        }
+        blocked
+    }
+
+    /// Share commands, equal commands are shared by all handles. Lines are
+    /// batched and limited by [CmdId] so commands of separate handles are
+    /// kept apart regardless.
     pub fn map_to_arc(self) -> Directory<Arc<Cmd>> {
+        let mut interned = HashSet::<Arc<Cmd>>::new();
+        let mut intern = |cmd: Cmd| {
+            if let Some(shared) = interned.get(&cmd) {
+                return Arc::clone(shared);
+            }
+            let shared = Arc::new(cmd);
+            interned.insert(Arc::clone(&shared));
+            shared
+        };
         Directory {
-            contents: self
-                .contents
-                .into_iter()
-                .map(|sub| {
-                    sub.into_iter()
-                        .map(|(key, value)| (key, Arc::from(value)))
-                        .collect()
-                })
-                .collect(),
+            ids: self.ids,
+            none: intern(self.none),
+            contents: self.contents.into_iter().map(&mut intern).collect(),
+            policy: self.policy,
+            trusted: self.trusted,
         }
     }
 
+    /// Create a new empty command.
+    ///
+    /// # Panics
+    /// If there are more commands than handles can refer to.
     pub fn new_handle(&mut self) -> Handle {
-        self.contents.push({
-            let mut btree = BTreeMap::new();
-            btree.insert(0, Cmd::default());
-            btree
-        });
-        Handle(self.contents.len() - 1, 0)
-    }
-
-    pub fn select_command(&mut self, handle: Handle, index: usize) -> Handle {
-        self.contents[handle.0].entry(index).or_default();
-        Handle(handle.0, index)
+        let handle = Handle(u32::try_from(self.contents.len()).expect("too many commands"));
+        let id = CMD_IDS.fetch_add(1, Ordering::Relaxed);
+        self.ids.push(CmdId(NonZeroU64::MIN.saturating_add(id)));
+        self.contents.push(Cmd::default());
+        handle
     }
 }
 
@@ -103,12 +687,17 @@ impl Default for Directory<Cmd> {
 }
 
 impl<T> Directory<T> {
+    /// Identity of command of handle, lines without a handle have none.
+    pub fn id(&self, handle: Option<Handle>) -> Option<CmdId> {
+        self.ids.get(handle?.idx()).copied()
+    }
+
     pub fn get(&self, handle: Handle) -> Option<&T> {
-        self.contents.get(handle.0)?.get(&handle.1)
+        self.contents.get(handle.idx())
     }
 
     pub fn get_mut(&mut self, handle: Handle) -> Option<&mut T> {
-        self.contents.get_mut(handle.0)?.get_mut(&handle.1)
+        self.contents.get_mut(handle.idx())
     }
 }
 
@@ -120,6 +709,15 @@ impl<T> ::core::ops::Index<Handle> for Directory<T> {
     }
 }
 
+impl<T> ::core::ops::Index<Option<Handle>> for Directory<T> {
+    type Output = T;
+
+    /// Command of handle, or the empty command if there is none.
+    fn index(&self, index: Option<Handle>) -> &Self::Output {
+        index.map_or(&self.none, |index| &self[index])
+    }
+}
+
 impl<T> ::core::ops::IndexMut<Handle> for Directory<T> {
     fn index_mut(&mut self, index: Handle) -> &mut Self::Output {
         self.get_mut(index).unwrap()
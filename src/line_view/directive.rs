@@ -1,6 +1,7 @@
+use ::core::{num::NonZeroUsize, time::Duration};
 use ::std::borrow::Cow;
 
-use crate::line_view::Import;
+use crate::line_view::{Import, cmd::Input, line_view::directive_reader::Encoding};
 
 #[derive(Debug, Clone, Default)]
 pub enum Directive<'line> {
@@ -21,9 +22,28 @@ pub enum Directive<'line> {
     },
     Exe(Cow<'line, str>),
     Arg(Cow<'line, str>),
+    Args(Vec<String>),
+    Cwd(Cow<'line, str>),
+    Env(Cow<'line, str>, Cow<'line, str>),
+    EnvClear,
+    Input(Input),
+    Encoding(Encoding),
+    Timeout(Duration),
+    Limit(NonZeroUsize),
+    Capture,
+    Batch,
+    Shell(Cow<'line, str>),
+    Confirm(Option<Cow<'line, str>>),
     Warning(Cow<'line, str>),
     Title(Cow<'line, str>),
     Subtitle(Cow<'line, str>),
+    SectionExe(Vec<String>),
+    Sandbox {
+        ro: Vec<String>,
+        rw: Vec<String>,
+        no_net: bool,
+    },
+    Tags(Vec<Cow<'line, str>>),
     Text(Cow<'line, str>),
     Comment(Cow<'line, str>),
     Import(Import<'line>),
@@ -54,14 +74,123 @@ impl<'line> Directive<'line> {
         Ok(match directive {
             "arg" => Self::Arg(require_payload("arg")?.into()),
 
+            "args" => {
+                let Some(payload) = payload else {
+                    return Err("directive args requires an argument".into());
+                };
+                let payload = payload.trim();
+                match ::shell_words::split(payload) {
+                    Ok(args) => Self::Args(args),
+                    Err(err) => {
+                        return Err(format!("could not split args \"{payload}\", {err}").into());
+                    }
+                }
+            }
+
             "exe" => Self::Exe(require_payload("exe")?.into()),
 
+            "cwd" => Self::Cwd(require_payload("cwd")?.into()),
+
+            "env" => {
+                let Some((key, value)) = require_payload("env")?.split_once('=') else {
+                    return Err("directive env requires an argument of the form KEY=VALUE".into());
+                };
+                Self::Env(key.into(), value.into())
+            }
+
+            "env-clear" => Self::EnvClear,
+
+            "input" => Self::Input(match require_payload("input")? {
+                "arg" => Input::Arg,
+                "stdin" => Input::Stdin,
+                "none" => Input::None,
+                other => {
+                    return Err(format!(
+                        "{other} is not a valid input, expected one of stdin, arg or none"
+                    )
+                    .into());
+                }
+            }),
+
+            "encoding" => Self::Encoding(require_payload("encoding")?.parse()?),
+
+            "timeout" => {
+                let payload = require_payload("timeout")?;
+                match payload.parse::<f64>().map(Duration::try_from_secs_f64) {
+                    Ok(Ok(timeout)) => Self::Timeout(timeout),
+                    _ => {
+                        return Err(format!("{payload} is not a valid timeout in seconds").into());
+                    }
+                }
+            }
+
+            "limit" => {
+                let payload = require_payload("limit")?;
+                match payload.parse::<NonZeroUsize>() {
+                    Ok(limit) => Self::Limit(limit),
+                    Err(err) => {
+                        return Err(format!("{payload} is not a valid limit, {err}").into());
+                    }
+                }
+            }
+
+            "capture" => Self::Capture,
+
+            "batch" => Self::Batch,
+
+            "shell" => Self::Shell(require_payload("shell")?.into()),
+
+            "confirm" => Self::Confirm(require_payload("confirm").ok().map(Cow::from)),
+
             "clean" => Self::Clean,
 
             "title" => Self::Title(require_payload("title")?.into()),
 
             "subtitle" => Self::Subtitle(require_payload("subtitle")?.into()),
 
+            "section-exe" => {
+                let payload = require_payload("section-exe")?;
+                match ::shell_words::split(payload) {
+                    Ok(words) if !words.is_empty() => Self::SectionExe(words),
+                    Ok(..) => return Err("directive section-exe requires an argument".into()),
+                    Err(err) => {
+                        return Err(
+                            format!("could not split section-exe \"{payload}\", {err}").into()
+                        );
+                    }
+                }
+            }
+
+            "sandbox" => {
+                let payload = payload.unwrap_or_default().trim();
+                let words = match ::shell_words::split(payload) {
+                    Ok(words) => words,
+                    Err(err) => {
+                        return Err(format!("could not split sandbox \"{payload}\", {err}").into());
+                    }
+                };
+                let (mut ro, mut rw, mut no_net) = (Vec::new(), Vec::new(), false);
+                for word in words {
+                    if word == "no-net" {
+                        no_net = true;
+                    } else if let Some(path) = word.strip_prefix("rw=") {
+                        rw.push(path.to_owned());
+                    } else {
+                        // paths are read-only unless specified otherwise
+                        ro.push(word.strip_prefix("ro=").map_or(word.clone(), String::from));
+                    }
+                }
+                Self::Sandbox { ro, rw, no_net }
+            }
+
+            "tags" => Self::Tags(
+                payload
+                    .unwrap_or_default()
+                    .split_whitespace()
+                    .map(Cow::Borrowed)
+                    .collect(),
+            ),
+
             "import" => Self::Import(Import::new_import(require_payload("import")?)),
 
             "lines" => Self::Import(Import::new_lines(require_payload("lines")?)),
@@ -99,6 +228,23 @@ impl<'line> Directive<'line> {
             }
         })
     }
+    /// Check if directive defines what a command executes.
+    pub const fn defines_command(&self) -> bool {
+        matches!(
+            self,
+            Self::Exe(..)
+                | Self::Arg(..)
+                | Self::Args(..)
+                | Self::Cwd(..)
+                | Self::Env(..)
+                | Self::EnvClear
+                | Self::Input(..)
+                | Self::Shell(..)
+                | Self::SectionExe(..)
+                | Self::Sandbox { .. }
+        )
+    }
+
     pub fn parse_directive(text: &'line str) -> Self {
         match Self::parse_directive_result(text) {
             Err(warn) => Self::Warning(warn),
@@ -120,4 +266,50 @@ impl<'line> Directive<'line> {
             Self::Text(text.into())
         }
     }
+
+    /// Copy any borrowed text, such that the directive may outlive the
+    /// line it was parsed from.
+    pub fn into_owned(self) -> Directive<'static> {
+        fn owned(text: Cow<'_, str>) -> Cow<'static, str> {
+            Cow::Owned(text.into_owned())
+        }
+        match self {
+            Self::Noop => Directive::Noop,
+            Self::Empty => Directive::Empty,
+            Self::Close => Directive::Close,
+            Self::Clean => Directive::Clean,
+            Self::DisplayWarnings => Directive::DisplayWarnings,
+            Self::IgnoreWarnings => Directive::IgnoreWarnings,
+            Self::IgnoreText => Directive::IgnoreText,
+            Self::Watch => Directive::Watch,
+            Self::Then => Directive::Then,
+            Self::Else => Directive::Else,
+            Self::Debug => Directive::Debug,
+            Self::EndMap { automatic } => Directive::EndMap { automatic },
+            Self::Exe(exe) => Directive::Exe(owned(exe)),
+            Self::Arg(arg) => Directive::Arg(owned(arg)),
+            Self::Args(args) => Directive::Args(args),
+            Self::Cwd(cwd) => Directive::Cwd(owned(cwd)),
+            Self::Env(key, value) => Directive::Env(owned(key), owned(value)),
+            Self::EnvClear => Directive::EnvClear,
+            Self::Input(input) => Directive::Input(input),
+            Self::Encoding(encoding) => Directive::Encoding(encoding),
+            Self::Timeout(timeout) => Directive::Timeout(timeout),
+            Self::Limit(limit) => Directive::Limit(limit),
+            Self::Capture => Directive::Capture,
+            Self::Batch => Directive::Batch,
+            Self::Shell(shell) => Directive::Shell(owned(shell)),
+            Self::Confirm(confirm) => Directive::Confirm(confirm.map(owned)),
+            Self::Warning(warning) => Directive::Warning(owned(warning)),
+            Self::Title(title) => Directive::Title(owned(title)),
+            Self::Subtitle(subtitle) => Directive::Subtitle(owned(subtitle)),
+            Self::SectionExe(words) => Directive::SectionExe(words),
+            Self::Sandbox { ro, rw, no_net } => Directive::Sandbox { ro, rw, no_net },
+            Self::Tags(tags) => Directive::Tags(tags.into_iter().map(owned).collect()),
+            Self::Text(text) => Directive::Text(owned(text)),
+            Self::Comment(comment) => Directive::Comment(owned(comment)),
+            Self::Import(import) => Directive::Import(import.into_owned()),
+            Self::Multiple(directives) => Directive::Multiple(directives),
+        }
+    }
 }
@@ -12,6 +12,10 @@ pub enum Error {
         program: String,
         args: Vec<String>,
     },
+    #[error("could not set up sandbox, {0}")]
+    Sandbox(String),
+    #[error("loading was cancelled")]
+    Cancelled,
 }
 
 struct ArgProxy<'a>(&'a Vec<String>);
@@ -1,12 +1,12 @@
 use ::std::{
     borrow::Cow,
     path::Path,
-    sync::{Arc, RwLock},
+    sync::{Arc, PoisonError, RwLock},
 };
 
 use crate::line_view::{
     Cmd, Directive, PathSet, cmd,
-    line_view::{line_map::DirectiveMapperChain, source::Source},
+    line_view::{file_cache::FileCache, line_map::DirectiveMapperChain, source::Source},
     provide,
 };
 
@@ -43,45 +43,54 @@ impl<'line> Import<'line> {
         }
     }
 
+    /// Copy borrowed path, such that the import may outlive the line it
+    /// was parsed from.
+    pub fn into_owned(self) -> Import<'static> {
+        Import {
+            file: Cow::Owned(self.file.into_owned()),
+            kind: self.kind,
+        }
+    }
+
     pub fn perform_import(
         self,
         parent: Source,
         imported: &mut PathSet,
         cmd_directory: &mut cmd::Directory<Cmd>,
-        provider: impl provide::Read,
+        cache: &FileCache<impl provide::Read>,
         home: Option<&Path>,
     ) -> ::core::result::Result<Source, Directive<'static>> {
         let Self { file, kind } = self;
+        let section = Arc::clone(&parent.section);
+        let tags = Arc::clone(&parent.tags);
         match kind {
-            ImportKind::Source => source(
-                &file,
-                parent.dir,
-                parent.cmd,
-                parent.sourced,
-                cmd_directory,
-                provider,
-                home,
-            ),
-            ImportKind::Import => {
-                import(&file, parent.dir, imported, cmd_directory, provider, home)
-            }
-            ImportKind::Lines => {
-                lines(&file, parent.dir, parent.cmd, cmd_directory, provider, home)
+            ImportKind::Source => {
+                source(&file, parent.dir, parent.cmd, parent.sourced, cache, home)
             }
+            ImportKind::Import => import(&file, parent.dir, imported, cmd_directory, cache, home),
+            ImportKind::Lines => lines(&file, parent.dir, parent.cmd, cache, home),
         }
+        // imported lines are placed in the section of the parent
+        .map(|source| Source {
+            outer_section: section.clone(),
+            section,
+            tags,
+            ..source
+        })
         .ok_or_else(|| Directive::Warning(format!("could not source/import/lines {file}").into()))
     }
 }
 
 fn import(
     line: &str,
-    dir: Arc<str>,
+    dir: Arc<Path>,
     imported: &mut PathSet,
     cmd_directory: &mut cmd::Directory<Cmd>,
-    provider: impl provide::Read,
+    cache: &FileCache<impl provide::Read>,
     home: Option<&Path>,
 ) -> Option<Source> {
-    let source = match Source::parse(line, &dir, cmd_directory, provider, home) {
+    // imports get their own command
+    let source = match Source::parse(line, &dir, cmd_directory.new_handle(), cache, home) {
         Ok(source) => source,
         Err(err) => {
             ::log::error!("\n{err}");
@@ -103,25 +112,19 @@ fn import(
 
 fn source(
     line: &str,
-    dir: Arc<str>,
+    dir: Arc<Path>,
     cmd: cmd::Handle,
     sourced: Arc<RwLock<PathSet>>,
-    cmd_directory: &mut cmd::Directory<Cmd>,
-    provider: impl provide::Read,
+    cache: &FileCache<impl provide::Read>,
     home: Option<&Path>,
 ) -> Option<Source> {
-    let source = match Source::parse(line, &dir, cmd_directory, provider, home) {
+    // sourced content keep command of parent
+    let source = match Source::parse(line, &dir, cmd, cache, home) {
         Ok(source) => Source {
             // sources gain source context of parent, while imports get their own
             sourced: Arc::clone(&sourced),
-            // sourced content keep command of parent
-            cmd,
-            // all of these are created for the source and not inherited
-            read: source.read,
-            path: source.path,
-            dir: source.dir,
-            line_map: source.line_map,
-            warning_watcher: source.warning_watcher,
+            // all else is created for the source and not inherited
+            ..source
         },
         Err(err) => {
             ::log::error!("\n{err}");
@@ -129,7 +132,7 @@ fn source(
         }
     };
 
-    let mut sourced = sourced.write().unwrap();
+    let mut sourced = sourced.write().unwrap_or_else(PoisonError::into_inner);
 
     if let Some(path) = &source.path {
         // skip if already sourced in this context
@@ -151,25 +154,19 @@ fn skip_directives(parsed: Directive<'_>) -> Directive<'_> {
 
 fn lines(
     line: &str,
-    dir: Arc<str>,
+    dir: Arc<Path>,
     cmd: cmd::Handle,
-    cmd_directory: &mut cmd::Directory<Cmd>,
-    provider: impl provide::Read,
+    cache: &FileCache<impl provide::Read>,
     home: Option<&Path>,
 ) -> Option<Source> {
-    // lines can be sourced however much is wanted since they cannot create cycles
-    match Source::parse(line, &dir, cmd_directory, provider, home) {
+    // lines can be sourced however much is wanted since they cannot create cycles,
+    // and inherit command from parent
+    match Source::parse(line, &dir, cmd, cache, home) {
         Ok(source) => Some(Source {
-            // lines inherit command from parent
-            cmd,
             // the special part about lines
             line_map: Some(DirectiveMapperChain::new(skip_directives, None, true)),
-            // all of these are newly created and not inherited
-            read: source.read,
-            path: source.path,
-            sourced: source.sourced,
-            dir: source.dir,
-            warning_watcher: source.warning_watcher,
+            // all else is newly created and not inherited
+            ..source
         }),
         Err(err) => {
             ::log::error!("\n{err}");
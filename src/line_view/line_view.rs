@@ -1,24 +1,25 @@
-mod directive_reader;
 mod directive_source;
 mod source_action;
 
+pub(crate) mod directive_reader;
+pub(crate) mod file_cache;
 pub(crate) mod line;
 pub(crate) mod line_map;
 pub(crate) mod source;
 
-use ::core::fmt::Debug;
+use ::core::{fmt::Debug, ops::ControlFlow};
 use ::std::{
     io::{BufRead, Cursor},
-    path::Path,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
 use rustc_hash::FxHashSet;
 
 use crate::line_view::{
-    Result,
-    cmd::{self, Cmd},
-    line_view::{line::Line, source::Source},
+    Error, Result,
+    cmd::{self, Cmd, Policy},
+    line_view::{file_cache::FileCache, line::Line, source::Source},
     provide,
 };
 
@@ -26,6 +27,8 @@ use crate::line_view::{
 pub struct LineView {
     title: String,
     lines: Vec<Line<Arc<Cmd>>>,
+    untrusted: Vec<Arc<Path>>,
+    blocked: Vec<PathBuf>,
 }
 
 /// Initial lines to construct line-view from.
@@ -34,28 +37,75 @@ pub enum RootLines<R> {
     /// Start with in-memory lines.
     Buffer(R),
     /// Start with lines read
-    Path(Arc<str>),
+    Path(Arc<Path>),
 }
 
+/// Amount of directives performed between reports of an incremental load.
+const BATCH_SIZE: usize = 1024;
+
 impl LineView {
     pub fn read_buf(
         buffer: impl 'static + BufRead + Debug,
         read_provider: impl provide::Read,
         home: Option<&Path>,
+        policy: Policy,
     ) -> Result<Self> {
-        Self::read_(RootLines::Buffer(buffer), read_provider, home)
+        Self::read_(
+            RootLines::Buffer(buffer),
+            read_provider,
+            home,
+            policy,
+            false,
+            |_, _| ControlFlow::Continue(()),
+        )
     }
     pub fn read_path(
-        path: Arc<str>,
+        path: Arc<Path>,
+        read_provider: impl provide::Read,
+        home: Option<&Path>,
+        policy: Policy,
+    ) -> Result<Self> {
+        Self::read_(
+            RootLines::<Cursor<&[u8]>>::Path(path),
+            read_provider,
+            home,
+            policy,
+            false,
+            |_, _| ControlFlow::Continue(()),
+        )
+    }
+    /// Read path, periodically passing the amount of lines read so far to
+    /// on_lines, and if preview is set the lines read since last call.
+    ///
+    /// Lines passed to on_lines have no commands and their view is titled
+    /// by the path, since both may be changed by later lines, the returned
+    /// view has them all resolved. If on_lines breaks, reading stops with
+    /// [Error::Cancelled]. It may be called with no new lines, to allow
+    /// cancellation while reading directives.
+    pub fn read_path_incremental(
+        path: Arc<Path>,
         read_provider: impl provide::Read,
         home: Option<&Path>,
+        policy: Policy,
+        preview: bool,
+        on_lines: impl FnMut(usize, Vec<Line<Arc<Cmd>>>) -> ControlFlow<()>,
     ) -> Result<Self> {
-        Self::read_(RootLines::<Cursor<&[u8]>>::Path(path), read_provider, home)
+        Self::read_(
+            RootLines::<Cursor<&[u8]>>::Path(path),
+            read_provider,
+            home,
+            policy,
+            preview,
+            on_lines,
+        )
     }
     fn read_(
         root: RootLines<impl 'static + BufRead + Debug>,
         read_provider: impl provide::Read,
         home: Option<&Path>,
+        policy: Policy,
+        preview: bool,
+        mut on_lines: impl FnMut(usize, Vec<Line<Arc<Cmd>>>) -> ControlFlow<()>,
     ) -> Result<Self> {
         // setup stack, and source set
         let mut sources = Vec::new();
@@ -63,7 +113,9 @@ impl LineView {
 
         let mut lines = Vec::new();
         let mut title = None;
-        let mut cmd_directory = cmd::Directory::new();
+        let mut cmd_directory = cmd::Directory::with_policy(policy);
+        // files used multiple times are only read and parsed once per load
+        let cache = FileCache::new(read_provider);
 
         let root_path;
         let root = match root {
@@ -72,23 +124,55 @@ impl LineView {
                 Source::with_buf_read(r, &mut cmd_directory)?
             }
             RootLines::Path(path) => {
-                let root = Source::open(path.clone(), &mut cmd_directory, &read_provider)?;
-                root_path = Some(path.clone());
-                imported.insert(path);
+                // sources of lines are canonical, as they are for imports
+                let canonical = path.canonicalize().map_or_else(|_| path.clone(), Arc::from);
+                let root = Source::open(canonical.clone(), &mut cmd_directory, &cache)?;
+                root_path = Some(path);
+                imported.insert(canonical);
                 root
             }
         };
 
         sources.push(root);
 
+        // lines sent before commands are known share an empty command
+        let preview_cmd = Arc::new(Cmd::default());
+        let preview_view = Arc::new(line::View {
+            title: root_path.as_ref().map_or_else(
+                || "No Title".into(),
+                |path| path.display().to_string().into(),
+            ),
+            root: root_path.clone().into(),
+        });
+        let mut sent = 0;
+        let mut performed = 0usize;
+
         while let Some(source) = sources.last_mut() {
+            performed += 1;
+            if performed.is_multiple_of(BATCH_SIZE) {
+                let batch = if preview {
+                    lines[sent..]
+                        .iter()
+                        .map(|line: &Line<Option<cmd::Handle>>| {
+                            line.preview(&preview_cmd, &preview_view)
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
+                sent = lines.len();
+                if on_lines(sent, batch).is_break() {
+                    return Err(Error::Cancelled);
+                }
+            }
+
             match source_action::SourceAction::perform(
                 source,
                 &mut imported,
                 &mut lines,
                 &mut title,
                 &mut cmd_directory,
-                &read_provider,
+                &cache,
                 home,
             )? {
                 source_action::SourceAction::Noop => {}
@@ -100,22 +184,45 @@ impl LineView {
         }
 
         let title = title.unwrap_or_else(|| {
-            root_path.map_or_else(|| "No Title".to_owned(), |path| path.to_string())
+            root_path
+                .as_ref()
+                .map_or_else(|| "No Title".to_owned(), |path| path.display().to_string())
+        });
+        let view = Arc::new(line::View {
+            title: title.as_str().into(),
+            root: root_path.into(),
         });
 
+        let blocked = cmd_directory.apply_allowlist().into_iter().collect();
+        let untrusted = cmd_directory.untrusted().cloned().collect();
         let cmd_directory = cmd_directory.map_to_arc();
         let lines = lines
             .into_iter()
-            .map(|line| line.map_to_arc_cmd(&cmd_directory))
+            .map(|line| line.map_to_arc_cmd(&cmd_directory, &view))
             .collect();
 
-        Ok(Self { lines, title })
+        Ok(Self {
+            title,
+            lines,
+            untrusted,
+            blocked,
+        })
     }
 
     pub fn title(&self) -> &str {
         &self.title
     }
 
+    /// Files whose commands were ignored since they are not trusted.
+    pub fn untrusted(&self) -> &[Arc<Path>] {
+        &self.untrusted
+    }
+
+    /// Programs that may not be executed according to the allowlist.
+    pub fn blocked(&self) -> &[PathBuf] {
+        &self.blocked
+    }
+
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
         self.into_iter()
     }
@@ -124,9 +231,28 @@ impl LineView {
         self.into_iter()
     }
 
+    pub const fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
     pub fn get(&self, index: usize) -> Option<&Line<Arc<Cmd>>> {
         self.lines.get(index)
     }
+
+    /// Lines of section started by title at index, up until the next title,
+    /// skipping empty lines and warnings.
+    pub fn section(&self, title: usize) -> impl Iterator<Item = &Line<Arc<Cmd>>> {
+        self.lines
+            .get(title + 1..)
+            .unwrap_or_default()
+            .iter()
+            .take_while(|line| !line.is_title())
+            .filter(|line| !line.is_warning() && !line.text().is_empty())
+    }
 }
 
 impl AsRef<LineView> for LineView {
@@ -146,6 +272,12 @@ where
     }
 }
 
+impl Extend<Line<Arc<Cmd>>> for LineView {
+    fn extend<T: IntoIterator<Item = Line<Arc<Cmd>>>>(&mut self, iter: T) {
+        self.lines.extend(iter);
+    }
+}
+
 impl IntoIterator for LineView {
     type Item = Line<Arc<Cmd>>;
 
@@ -1,18 +1,144 @@
-use ::core::fmt::Debug;
-use ::std::io::BufRead;
+use ::core::{fmt::Debug, str::FromStr};
+use ::std::{borrow::Cow, io::BufRead};
 
 use crate::line_view::line_view::directive_source::DirectiveSource;
 use crate::line_view::{Directive, Result};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// Detect encoding by byte order mark, returning the encoding and
+    /// the length of the mark.
+    fn detect(head: &[u8]) -> (Self, usize) {
+        match head {
+            [0xef, 0xbb, 0xbf, ..] => (Self::Utf8, 3),
+            [0xff, 0xfe, ..] => (Self::Utf16Le, 2),
+            [0xfe, 0xff, ..] => (Self::Utf16Be, 2),
+            _ => (Self::Utf8, 0),
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "latin-1",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+        }
+    }
+
+    /// Read a line including terminator into buf, returning the amount of
+    /// bytes read.
+    fn read_line(self, read: &mut impl BufRead, buf: &mut Vec<u8>) -> ::std::io::Result<usize> {
+        let newline = match self {
+            Self::Utf8 | Self::Latin1 => return read.read_until(b'\n', buf),
+            Self::Utf16Le => [b'\n', 0],
+            Self::Utf16Be => [0, b'\n'],
+        };
+        let start = buf.len();
+        let mut unit = [0; 2];
+        loop {
+            // a trailing odd byte is kept and decoded as invalid
+            match read.read(&mut unit[..1])? {
+                0 => break,
+                _ => buf.push(unit[0]),
+            }
+            match read.read(&mut unit[1..])? {
+                0 => break,
+                _ => buf.push(unit[1]),
+            }
+            if unit == newline {
+                break;
+            }
+        }
+        Ok(buf.len() - start)
+    }
+
+    /// Decode bytes, invalid sequences are replaced in which case true is
+    /// also returned.
+    fn decode(self, bytes: &[u8]) -> (Cow<'_, str>, bool) {
+        let units = |from: fn([u8; 2]) -> u16| {
+            let mut is_lossy = !bytes.len().is_multiple_of(2);
+            let text = char::decode_utf16(
+                bytes
+                    .chunks_exact(2)
+                    .map(|chunk| from([chunk[0], chunk[1]])),
+            )
+            .map(|c| {
+                c.unwrap_or_else(|_| {
+                    is_lossy = true;
+                    char::REPLACEMENT_CHARACTER
+                })
+            })
+            .collect::<String>();
+            (Cow::Owned(text), is_lossy)
+        };
+        match self {
+            Self::Utf8 => match str::from_utf8(bytes) {
+                Ok(text) => (Cow::Borrowed(text), false),
+                Err(..) => (String::from_utf8_lossy(bytes), true),
+            },
+            Self::Latin1 => (
+                Cow::Owned(bytes.iter().copied().map(char::from).collect()),
+                false,
+            ),
+            Self::Utf16Le => units(u16::from_le_bytes),
+            Self::Utf16Be => units(u16::from_be_bytes),
+        }
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::core::result::Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "utf-8" | "utf8" => Self::Utf8,
+            "latin-1" | "latin1" | "iso-8859-1" => Self::Latin1,
+            "utf-16le" | "utf16le" => Self::Utf16Le,
+            "utf-16be" | "utf16be" => Self::Utf16Be,
+            _ => {
+                return Err(format!(
+                    "{s} is not a valid encoding, expected one of utf-8, latin-1, utf-16le or utf-16be"
+                ));
+            }
+        })
+    }
+}
+
 #[derive(Debug)]
-pub struct DirectiveReader<R>(R, usize, String);
+pub struct DirectiveReader<R> {
+    read: R,
+    pos: usize,
+    buf: String,
+    bytes: Vec<u8>,
+    /// Encoding of file, detected on first read.
+    encoding: Option<Encoding>,
+    /// Amount of lines with invalid bytes, the first such line and the
+    /// encoding it was decoded with.
+    lossy: Option<(usize, usize, Encoding)>,
+}
 
 impl<R> DirectiveReader<R>
 where
     R: BufRead,
 {
     pub const fn new(read: R) -> Self {
-        Self(read, 0, String::new())
+        Self {
+            read,
+            pos: 0,
+            buf: String::new(),
+            bytes: Vec::new(),
+            encoding: None,
+            lossy: None,
+        }
     }
 }
 
@@ -21,18 +147,90 @@ where
     R: Debug + BufRead,
 {
     fn read(&mut self) -> Result<(usize, Directive<'_>)> {
-        let Self(read, pos, buf) = self;
+        let Self {
+            read,
+            pos,
+            buf,
+            bytes,
+            encoding,
+            lossy,
+        } = self;
 
         let pos = {
             *pos += 1;
             *pos - 1
         };
 
-        buf.clear();
-        if read.read_line(buf)? == 0 {
+        let encoding = match encoding {
+            Some(encoding) => encoding,
+            None => {
+                let (detected, bom) = Encoding::detect(read.fill_buf()?);
+                read.consume(bom);
+                encoding.insert(detected)
+            }
+        };
+
+        bytes.clear();
+        if encoding.read_line(read, bytes)? == 0 {
+            // a single warning is given per file, before it is closed
+            if let Some((count, first, decoded)) = lossy.take() {
+                let lines = if count == 1 { "line" } else { "lines" };
+                return Ok((
+                    first,
+                    Directive::Warning(Cow::Owned(format!(
+                        "{count} {lines} not valid {}, starting at line {}, invalid bytes were replaced, use #-encoding to set encoding of file",
+                        decoded.name(),
+                        first + 1,
+                    ))),
+                ));
+            }
             return Ok((pos, Directive::Close));
         }
 
-        Ok((pos, Directive::parse_line(buf)))
+        let (text, is_lossy) = encoding.decode(bytes);
+        buf.clear();
+        buf.push_str(&text);
+        // both \n and \r\n terminated lines are accepted
+        if buf.ends_with('\n') {
+            buf.pop();
+            if buf.ends_with('\r') {
+                buf.pop();
+            }
+        }
+
+        if is_lossy {
+            let (count, ..) = lossy.get_or_insert((0, pos, *encoding));
+            *count += 1;
+        }
+
+        Ok((pos, parse(buf, encoding)))
+    }
+}
+
+/// Parse line, applying encoding directives to the reader instead of
+/// passing them on.
+///
+/// A switch applies to the lines following the directive. Since the
+/// directive itself has to be readable it only switches between ascii
+/// compatible encodings, utf-16 is only detected by byte order mark.
+fn parse<'buf>(buf: &'buf str, encoding: &mut Encoding) -> Directive<'buf> {
+    match Directive::parse_line(buf) {
+        Directive::Encoding(new @ (Encoding::Utf16Le | Encoding::Utf16Be)) => {
+            Directive::Warning(Cow::Owned(format!(
+                "#-encoding cannot switch to {}, utf-16 files are detected by byte order mark",
+                new.name()
+            )))
+        }
+        Directive::Encoding(..) if matches!(encoding, Encoding::Utf16Le | Encoding::Utf16Be) => {
+            Directive::Warning(Cow::Owned(format!(
+                "#-encoding cannot switch from {}, file has a byte order mark",
+                encoding.name()
+            )))
+        }
+        Directive::Encoding(new) => {
+            *encoding = new;
+            Directive::Noop
+        }
+        directive => directive,
     }
 }
@@ -0,0 +1,140 @@
+use ::core::{cell::RefCell, fmt::Debug};
+use ::std::{
+    io::{Cursor, Read as _},
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::SystemTime,
+};
+
+use ::hashbrown::HashMap;
+
+use crate::line_view::{
+    Directive, Result,
+    line_view::{
+        directive_reader::DirectiveReader,
+        directive_source::{DirectiveSource, DirectiveStream},
+    },
+    provide,
+};
+
+/// Canonical path of a file and when it was modified, if known.
+type Key = (Arc<Path>, Option<SystemTime>);
+
+/// Directives of a file, in the order they were read.
+type Directives = Arc<[(usize, Directive<'static>)]>;
+
+#[derive(Debug)]
+enum File {
+    Read(Arc<[u8]>),
+    /// File has been read to the end after being opened more than once.
+    Parsed(Directives),
+}
+
+/// Files read during a single load, such that files used multiple times are
+/// provided once and parsed at most twice. Directives are only kept for
+/// files opened more than once, since most files are not.
+#[derive(Debug)]
+pub struct FileCache<P> {
+    provider: P,
+    files: Rc<RefCell<HashMap<Key, File>>>,
+}
+
+impl<P> FileCache<P>
+where
+    P: provide::Read,
+{
+    pub fn new(provider: P) -> Self {
+        Self {
+            provider,
+            files: Rc::default(),
+        }
+    }
+
+    /// Open directives of file at canonical path.
+    pub fn open(&self, path: &Arc<Path>) -> Result<DirectiveStream> {
+        // a file changed during the load is read again
+        let modified = path.metadata().and_then(|meta| meta.modified()).ok();
+        let key = (Arc::clone(path), modified);
+
+        let mut files = self.files.borrow_mut();
+        let bytes = match files.get(&key) {
+            Some(File::Parsed(directives)) => {
+                return Ok(DirectiveStream::new(Replay {
+                    directives: Arc::clone(directives),
+                    next: 0,
+                }));
+            }
+            Some(File::Read(bytes)) => Arc::clone(bytes),
+            None => {
+                let mut bytes = Vec::new();
+                self.provider.provide(path)?.read_to_end(&mut bytes)?;
+                let bytes = Arc::<[u8]>::from(bytes);
+                files.insert(key, File::Read(Arc::clone(&bytes)));
+                return Ok(DirectiveStream::new(DirectiveReader::new(Cursor::new(
+                    bytes,
+                ))));
+            }
+        };
+
+        Ok(DirectiveStream::new(Record {
+            read: DirectiveReader::new(Cursor::new(bytes)),
+            recorded: Vec::new(),
+            key,
+            files: Rc::clone(&self.files),
+        }))
+    }
+}
+
+/// Read directives, keeping them for the file once it has been read to the
+/// end.
+struct Record {
+    read: DirectiveReader<Cursor<Arc<[u8]>>>,
+    recorded: Vec<(usize, Directive<'static>)>,
+    key: Key,
+    files: Rc<RefCell<HashMap<Key, File>>>,
+}
+
+impl Debug for Record {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.debug_struct("Record")
+            .field("read", &self.read)
+            .field("recorded", &self.recorded.len())
+            .field("key", &self.key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DirectiveSource for Record {
+    fn read(&mut self) -> Result<(usize, Directive<'_>)> {
+        let (position, directive) = self.read.read()?;
+        self.recorded
+            .push((position, directive.clone().into_owned()));
+
+        if matches!(directive, Directive::Close) {
+            let directives = ::core::mem::take(&mut self.recorded).into();
+            self.files
+                .borrow_mut()
+                .insert(self.key.clone(), File::Parsed(directives));
+        }
+
+        Ok((position, directive))
+    }
+}
+
+/// Directives of a file already read.
+#[derive(Debug)]
+struct Replay {
+    directives: Directives,
+    next: usize,
+}
+
+impl DirectiveSource for Replay {
+    fn read(&mut self) -> Result<(usize, Directive<'_>)> {
+        // the last directive is always a close, which is repeated
+        let idx = self.next.min(self.directives.len() - 1);
+        self.next += 1;
+        let (position, directive) = &self.directives[idx];
+        Ok((*position, directive.clone()))
+    }
+}
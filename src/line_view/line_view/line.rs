@@ -1,7 +1,10 @@
 use ::core::fmt::Display;
-use ::std::sync::Arc;
+use ::std::{path::Path, sync::Arc};
 
-use crate::line_view::{Cmd, Result, cmd};
+use crate::line_view::{
+    Cmd,
+    cmd::{self, CmdId, Invocation},
+};
 
 #[derive(Debug, Clone, Copy, Default)]
 enum Kind {
@@ -13,27 +16,43 @@ enum Kind {
 
 #[derive(Debug, Clone)]
 pub enum Source {
-    File(Arc<str>),
+    File(Arc<Path>),
     Mem,
 }
 
+impl Source {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Source::File(path) => Some(path),
+            Source::Mem => None,
+        }
+    }
+
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Source::File(..) => "FILE",
+            Source::Mem => "MEM",
+        }
+    }
+}
+
 impl Display for Source {
     fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
         match self {
-            Source::File(src) => write!(f, "FILE:{}", src),
+            Source::File(src) => write!(f, "FILE:{}", src.display()),
             Source::Mem => f.write_str("MEM"),
         }
     }
 }
 
-impl From<Arc<str>> for Source {
-    fn from(value: Arc<str>) -> Self {
+impl From<Arc<Path>> for Source {
+    fn from(value: Arc<Path>) -> Self {
         Self::File(value)
     }
 }
 
-impl From<&Arc<str>> for Source {
-    fn from(value: &Arc<str>) -> Self {
+impl From<&Arc<Path>> for Source {
+    fn from(value: &Arc<Path>) -> Self {
         Self::File(Arc::clone(value))
     }
 }
@@ -51,6 +70,36 @@ where
     }
 }
 
+/// Where in a view a line was read.
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// Directory of file defining line.
+    pub dir: Arc<Path>,
+    /// Subtitles line is placed under, outermost first.
+    pub section: Arc<[Arc<str>]>,
+    /// Tags of line.
+    pub tags: Arc<[Arc<str>]>,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            dir: Arc::from(Path::new("")),
+            section: Arc::from([]),
+            tags: Arc::from([]),
+        }
+    }
+}
+
+/// View a line is part of.
+#[derive(Debug, Clone)]
+pub struct View {
+    /// Title of view.
+    pub title: Arc<str>,
+    /// Root of view.
+    pub root: Source,
+}
+
 #[derive(Debug, Clone)]
 pub struct Builder<T, P> {
     source: T,
@@ -58,6 +107,8 @@ pub struct Builder<T, P> {
     text: String,
     cmd: Option<cmd::Handle>,
     kind: Kind,
+    index: usize,
+    context: Context,
 }
 
 impl Builder<(), ()> {
@@ -68,6 +119,8 @@ impl Builder<(), ()> {
             text: String::new(),
             cmd: None,
             kind: Kind::default(),
+            index: 0,
+            context: Context::default(),
         }
     }
 }
@@ -79,6 +132,8 @@ impl<T, P> Builder<T, P> {
             text,
             cmd,
             kind,
+            index,
+            context,
             ..
         } = self;
         Builder {
@@ -87,6 +142,8 @@ impl<T, P> Builder<T, P> {
             text,
             cmd,
             kind,
+            index,
+            context,
         }
     }
 
@@ -96,6 +153,8 @@ impl<T, P> Builder<T, P> {
             text,
             cmd,
             kind,
+            index,
+            context,
             ..
         } = self;
         Builder {
@@ -104,9 +163,19 @@ impl<T, P> Builder<T, P> {
             text,
             cmd,
             kind,
+            index,
+            context,
         }
     }
 
+    pub fn index(self, index: usize) -> Self {
+        Self { index, ..self }
+    }
+
+    pub fn context(self, context: Context) -> Self {
+        Self { context, ..self }
+    }
+
     pub fn text(self, text: String) -> Self {
         Self { text, ..self }
     }
@@ -134,20 +203,27 @@ impl<T, P> Builder<T, P> {
 }
 
 impl Builder<Source, usize> {
-    pub fn build(self, cmd_directory: &mut cmd::Directory<Cmd>) -> Line<cmd::Handle> {
+    /// Build line, lines without a command have none.
+    pub fn build(self) -> Line<Option<cmd::Handle>> {
         let Self {
             source,
             position,
             text,
             cmd,
             kind,
+            index,
+            context,
         } = self;
         Line {
             text,
             source,
             position,
-            cmd: cmd.unwrap_or_else(|| cmd_directory.new_handle()),
+            cmd,
             kind,
+            index,
+            context,
+            view: None,
+            cmd_id: None,
         }
     }
 }
@@ -159,6 +235,11 @@ pub struct Line<C> {
     position: usize,
     cmd: C,
     kind: Kind,
+    index: usize,
+    context: Context,
+    view: Option<Arc<View>>,
+    /// Identity of command, set once commands are shared.
+    cmd_id: Option<CmdId>,
 }
 
 impl<C> Line<C> {
@@ -181,34 +262,86 @@ impl<C> Line<C> {
     pub const fn is_warning(&self) -> bool {
         matches!(self.kind, Kind::Warning)
     }
+
+    pub const fn cmd(&self) -> &C {
+        &self.cmd
+    }
+
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    pub const fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn view(&self) -> Option<&View> {
+        self.view.as_deref()
+    }
 }
 
-impl Line<cmd::Handle> {
-    pub fn map_to_arc_cmd(self, cmd_directory: &cmd::Directory<Arc<Cmd>>) -> Line<Arc<Cmd>> {
+impl Line<Option<cmd::Handle>> {
+    pub fn map_to_arc_cmd(
+        self,
+        cmd_directory: &cmd::Directory<Arc<Cmd>>,
+        view: &Arc<View>,
+    ) -> Line<Arc<Cmd>> {
         let Self {
             text,
             source,
             position,
             cmd,
             kind,
+            index,
+            context,
+            ..
         } = self;
         Line::<Arc<Cmd>> {
             text,
             source,
             position,
             kind,
+            index,
+            context,
+            view: Some(Arc::clone(view)),
+            cmd_id: cmd_directory.id(cmd),
             cmd: cmd_directory[cmd].clone(),
         }
     }
+
+    /// Command of line, a new command is created if it has none.
+    pub fn cmd_or_new(&mut self, cmd_directory: &mut cmd::Directory<Cmd>) -> cmd::Handle {
+        *self.cmd.get_or_insert_with(|| cmd_directory.new_handle())
+    }
+
+    /// Copy of line using cmd in place of its own command, for showing lines
+    /// before all commands are known.
+    pub fn preview(&self, cmd: &Arc<Cmd>, view: &Arc<View>) -> Line<Arc<Cmd>> {
+        Line::<Arc<Cmd>> {
+            text: self.text.clone(),
+            source: self.source.clone(),
+            position: self.position,
+            kind: self.kind,
+            index: self.index,
+            context: self.context.clone(),
+            view: Some(Arc::clone(view)),
+            cmd: Arc::clone(cmd),
+            cmd_id: None,
+        }
+    }
 }
 
 impl Line<Arc<Cmd>> {
+    /// Identity of command, lines are batched and limited by it.
+    pub const fn cmd_id(&self) -> Option<CmdId> {
+        self.cmd_id
+    }
+
     pub fn has_command(&self) -> bool {
         !self.cmd.is_empty()
     }
 
-    pub fn execute(&self) -> Result {
-        self.cmd
-            .execute(self.position, self.source.clone(), [self.text()])
+    pub fn invocation(&self) -> Option<Invocation> {
+        self.cmd.invocation(self)
     }
 }
@@ -1,8 +1,8 @@
 use ::core::{cell::RefCell, fmt::Debug};
 use ::std::{
     borrow::Cow,
-    io::{BufRead, BufReader},
-    path::Path,
+    io::BufRead,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{Arc, RwLock},
 };
@@ -15,6 +15,7 @@ use crate::line_view::{
         Cmd,
         directive_reader::DirectiveReader,
         directive_source::{DirectiveSource, DirectiveStream},
+        file_cache::FileCache,
         line_map::DirectiveMapperChain,
     },
     path_ext::PathExt as _,
@@ -56,31 +57,42 @@ impl Watch {
 #[derive(Debug)]
 pub struct Source {
     pub read: DirectiveStream,
-    pub path: Option<Arc<str>>,
+    pub path: Option<Arc<Path>>,
     pub cmd: cmd::Handle,
     pub sourced: Arc<RwLock<PathSet>>,
-    pub dir: Arc<str>,
+    pub dir: Arc<Path>,
+    pub outer_section: Arc<[Arc<str>]>,
+    pub section: Arc<[Arc<str>]>,
+    /// Index of line of current subtitle, which section commands are set on.
+    pub subtitle: Option<usize>,
+    pub tags: Arc<[Arc<str>]>,
     pub warning_watcher: Rc<RefCell<Watch>>,
     pub line_map: Option<DirectiveMapperChain>,
 }
 
 impl Source {
-    pub fn new(path: Option<Arc<str>>, cmd_directory: &mut cmd::Directory<Cmd>) -> Self {
+    pub fn new(path: Option<Arc<Path>>, cmd: cmd::Handle) -> Self {
         Self {
             read: DirectiveStream::new(NullReader),
             dir: if let Some(path) = &path {
-                let mut dir = AsRef::<Path>::as_ref(path.as_ref()).to_path_buf();
-                dir.pop();
-                dir.to_string_lossy().into()
+                path.parent().unwrap_or(path).into()
             } else {
+                // relative paths are resolved against the current directory
+                // when used, even if it cannot be queried now
                 ::std::env::current_dir()
-                    .unwrap_or_else(|err| panic!("could not get current directory, {err}"))
-                    .to_string_lossy()
+                    .unwrap_or_else(|err| {
+                        ::log::warn!("could not get current directory, {err}");
+                        PathBuf::from(".")
+                    })
                     .into()
             },
             path,
+            outer_section: Arc::from([]),
+            section: Arc::from([]),
+            subtitle: None,
+            tags: Arc::from([]),
             sourced: Default::default(),
-            cmd: cmd_directory.new_handle(),
+            cmd,
             warning_watcher: Default::default(),
             line_map: None,
         }
@@ -93,6 +105,10 @@ impl Source {
             cmd: self.cmd,
             sourced: self.sourced.clone(),
             dir: self.dir.clone(),
+            outer_section: self.outer_section.clone(),
+            section: self.section.clone(),
+            subtitle: self.subtitle,
+            tags: self.tags.clone(),
             warning_watcher: self.warning_watcher.clone(),
             line_map: self.line_map.clone(),
         }
@@ -106,35 +122,33 @@ impl Source {
             read: buf_reader
                 .pipe(DirectiveReader::new)
                 .pipe(DirectiveStream::new),
-            ..Source::new(None, cmd_directory)
+            ..Source::new(None, cmd_directory.new_handle())
         })
     }
 
+    /// Open file at canonical path.
     pub fn open(
-        path: Arc<str>,
+        path: Arc<Path>,
         cmd_directory: &mut cmd::Directory<Cmd>,
-        provider: impl provide::Read,
+        cache: &FileCache<impl provide::Read>,
     ) -> Result<Self> {
         Ok(Source {
-            read: provider
-                .provide(path.as_ref())?
-                .pipe(BufReader::new)
-                .pipe(DirectiveReader::new)
-                .pipe(DirectiveStream::new),
-            ..Source::new(Some(path), cmd_directory)
+            read: cache.open(&path)?,
+            ..Source::new(Some(path), cmd_directory.new_handle())
         })
     }
 
+    /// Parse path of an import, lines of which use command cmd.
     pub fn parse(
         line: &str,
-        dir: &str,
-        cmd_directory: &mut cmd::Directory<Cmd>,
-        provider: impl provide::Read,
+        dir: &Path,
+        cmd: cmd::Handle,
+        cache: &FileCache<impl provide::Read>,
         home: Option<&Path>,
     ) -> ParseResult<Self> {
         let line = escape_path(line, home)?;
 
-        let path = line.canonicalize_at(dir.as_ref()).map_err(|err| {
+        let path = line.canonicalize_at(dir).map_err(|err| {
             Cow::Owned(format!(
                 "could not canonicalize path, {}, {err}",
                 line.display()
@@ -146,8 +160,13 @@ impl Source {
             return Err(Cow::from(format!("could not find {}", line.display())));
         }
 
-        Source::open(path.to_string_lossy().into(), cmd_directory, provider)
-            .map_err(|err| Cow::from(format!("could not create source, {err}")))
+        let path = Arc::<Path>::from(path);
+        Ok(Source {
+            read: cache
+                .open(&path)
+                .map_err(|err| Cow::from(format!("could not create source, {err}")))?,
+            ..Source::new(Some(path), cmd)
+        })
     }
 }
 
@@ -6,10 +6,11 @@ use ::std::{
 };
 
 use crate::line_view::{
-    Cmd, Directive, PathSet, Result, cmd,
+    Cmd, Directive, PathSet, Result, cmd, escape_path,
     line_view::{
         Source,
         directive_source::DirectiveSource,
+        file_cache::FileCache,
         line::{self, Line},
     },
     provide,
@@ -121,8 +122,9 @@ fn directive_debug(line: Directive<'_>) -> Directive<'_> {
 }
 
 struct Lines<'lines> {
-    pub lines: &'lines mut Vec<Line<cmd::Handle>>,
-    pub path: Option<&'lines Arc<str>>,
+    pub lines: &'lines mut Vec<Line<Option<cmd::Handle>>>,
+    pub path: Option<&'lines Arc<Path>>,
+    pub context: line::Context,
     pub cmd: cmd::Handle,
     pub warning_watcher: &'lines RefCell<Watch>,
     pub position: usize,
@@ -133,38 +135,28 @@ impl Lines<'_> {
         line::Builder::new()
             .source(self.path.into())
             .position(self.position)
+            .index(self.lines.len())
+            .context(self.context.clone())
     }
 
-    fn push_warning(&mut self, text: Cow<'_, str>, cmd_directory: &mut cmd::Directory<Cmd>) {
+    fn push_warning(&mut self, text: Cow<'_, str>) {
         if let Watch::Watching { occured } = &mut *self.warning_watcher.borrow_mut() {
             occured.push(text.to_string())
         } else {
-            self.lines.push(
-                self.builder()
-                    .warning()
-                    .text(text.into())
-                    .build(cmd_directory),
-            );
+            self.lines
+                .push(self.builder().warning().text(text.into()).build());
         }
     }
-    fn push_subtitle(&mut self, text: Cow<'_, str>, cmd_directory: &mut cmd::Directory<Cmd>) {
-        self.lines.push(
-            self.builder()
-                .title()
-                .text(text.into())
-                .build(cmd_directory),
-        );
+    fn push_subtitle(&mut self, text: Cow<'_, str>) {
+        self.lines
+            .push(self.builder().title().text(text.into()).build());
     }
-    fn push_line(&mut self, text: Cow<'_, str>, cmd_directory: &mut cmd::Directory<Cmd>) {
-        self.lines.push(
-            self.builder()
-                .text(text.into())
-                .cmd(self.cmd)
-                .build(cmd_directory),
-        );
+    fn push_line(&mut self, text: Cow<'_, str>) {
+        self.lines
+            .push(self.builder().text(text.into()).cmd(self.cmd).build());
     }
-    fn push_empty(&mut self, cmd_directory: &mut cmd::Directory<Cmd>) {
-        self.lines.push(self.builder().build(cmd_directory));
+    fn push_empty(&mut self) {
+        self.lines.push(self.builder().build());
     }
 }
 
@@ -179,10 +171,10 @@ impl SourceAction {
     pub fn perform(
         source: &mut Source,
         imported: &mut PathSet,
-        lines: &mut Vec<Line<cmd::Handle>>,
+        lines: &mut Vec<Line<Option<cmd::Handle>>>,
         title: &mut Option<String>,
         cmd_directory: &mut cmd::Directory<Cmd>,
-        provider: impl provide::Read,
+        cache: &FileCache<impl provide::Read>,
         home: Option<&Path>,
     ) -> Result<SourceAction> {
         let shallow = source.shallow();
@@ -190,8 +182,13 @@ impl SourceAction {
             read,
             path,
             cmd,
+            dir,
             line_map,
             warning_watcher,
+            outer_section,
+            section,
+            subtitle,
+            tags,
             ..
         } = source;
 
@@ -203,6 +200,11 @@ impl SourceAction {
         let mut lines = Lines {
             lines,
             path,
+            context: line::Context {
+                dir: dir.clone(),
+                section: section.clone(),
+                tags: tags.clone(),
+            },
             position,
             cmd: *cmd,
             warning_watcher,
@@ -215,8 +217,13 @@ impl SourceAction {
             directive
         };
 
+        // commands of untrusted files are never defined
+        if directive.defines_command() && !cmd_directory.may_define(path) {
+            return Ok(SourceAction::Noop);
+        }
+
         match directive {
-            Directive::Noop | Directive::Comment(..) => {}
+            Directive::Noop | Directive::Comment(..) | Directive::Encoding(..) => {}
             Directive::Close => {
                 return Ok(SourceAction::Pop);
             }
@@ -224,11 +231,77 @@ impl SourceAction {
                 *cmd = cmd_directory.new_handle();
             }
             Directive::Exe(exe) => {
-                cmd_directory[*cmd].exe(PathBuf::from(exe.as_ref()));
+                // commands run in the directory of the file defining them by default
+                cmd_directory[*cmd]
+                    .exe(PathBuf::from(exe.as_ref()))
+                    .default_cwd(|| dir.to_path_buf());
             }
             Directive::Arg(arg) => {
                 cmd_directory[*cmd].arg(arg.into());
             }
+            Directive::Args(args) => {
+                let cmd = &mut cmd_directory[*cmd];
+                for arg in args {
+                    cmd.arg(arg);
+                }
+            }
+            Directive::Cwd(cwd) => match escape_path(&cwd, home) {
+                Ok(cwd) => {
+                    cmd_directory[*cmd].cwd(dir.join(cwd));
+                }
+                Err(err) => lines.push_warning(
+                    format!("could not use {cwd} as working directory, {err}").into(),
+                ),
+            },
+            Directive::Sandbox { ro, rw, no_net } => {
+                let mut resolve = |paths: Vec<String>| {
+                    paths
+                        .into_iter()
+                        .filter_map(|path| match escape_path(&path, home) {
+                            Ok(resolved) => Some(dir.join(resolved)),
+                            Err(err) => {
+                                lines.push_warning(
+                                    format!("could not sandbox {path}, {err}").into(),
+                                );
+                                None
+                            }
+                        })
+                        .collect()
+                };
+                let ro = resolve(ro);
+                let rw = resolve(rw);
+                cmd_directory[*cmd].sandbox(ro, rw, no_net);
+            }
+            Directive::Env(key, value) => {
+                cmd_directory[*cmd].env(key.into(), value.into());
+            }
+            Directive::EnvClear => {
+                cmd_directory[*cmd].env_clear();
+            }
+            Directive::Input(input) => {
+                cmd_directory[*cmd].input(input);
+            }
+            Directive::Timeout(timeout) => {
+                cmd_directory[*cmd].timeout(timeout);
+            }
+            Directive::Limit(limit) => {
+                cmd_directory[*cmd].limit(limit);
+            }
+            Directive::Capture => {
+                cmd_directory[*cmd].capture();
+            }
+            Directive::Batch => {
+                cmd_directory[*cmd].batch();
+            }
+            Directive::Confirm(message) => {
+                cmd_directory[*cmd]
+                    .confirm(message.map_or_else(|| "Execute command?".to_owned(), String::from));
+            }
+            Directive::Shell(script) => {
+                cmd_directory[*cmd]
+                    .shell(script.into())
+                    .default_cwd(|| dir.to_path_buf());
+            }
             Directive::Watch => {
                 let is_sleeping = warning_watcher.borrow().is_sleeping();
                 if is_sleeping {
@@ -236,7 +309,6 @@ impl SourceAction {
                 } else {
                     lines.push_warning(
                         "watch called multiple times before else or then block".into(),
-                        cmd_directory,
                     );
                 }
             }
@@ -249,7 +321,6 @@ impl SourceAction {
                 } else {
                     lines.push_warning(
                         "then blocks need to be placed somewhere after a watch directive".into(),
-                        cmd_directory,
                     );
                 }
             }
@@ -262,15 +333,11 @@ impl SourceAction {
                 } else {
                     lines.push_warning(
                         "else blocks need to be placed somewhere after a watch directive".into(),
-                        cmd_directory,
                     );
                 }
             }
             Directive::DisplayWarnings => {
-                lines.push_warning(
-                    "warnings can only be displayed in else blocks".into(),
-                    cmd_directory,
-                );
+                lines.push_warning("warnings can only be displayed in else blocks".into());
             }
             Directive::IgnoreWarnings => {
                 fn ignore_warnings(directive: Directive<'_>) -> Directive<'_> {
@@ -298,21 +365,21 @@ impl SourceAction {
                         *line_map = line_map_ref.prev();
                     } else if automatic {
                         let msg = "EndMap directive was issued automatically whilst a manual end directive was required";
-                        lines.push_warning(msg.into(), cmd_directory);
+                        lines.push_warning(msg.into());
                     } else {
                         let msg = "end directive was given when an automatic EndMap directive was required";
-                        lines.push_warning(msg.into(), cmd_directory);
+                        lines.push_warning(msg.into());
                     }
                 } else if automatic {
                     let msg = "EndMap directive was issued automatically with no LineMap in use";
-                    lines.push_warning(msg.into(), cmd_directory);
+                    lines.push_warning(msg.into());
                 } else {
                     let msg = "end directive used with nothing to end";
-                    lines.push_warning(msg.into(), cmd_directory);
+                    lines.push_warning(msg.into());
                 }
             }
             Directive::Warning(warn) => {
-                lines.push_warning(warn, cmd_directory);
+                lines.push_warning(warn);
             }
             Directive::Title(text) => {
                 if title.is_none() {
@@ -320,16 +387,41 @@ impl SourceAction {
                 }
             }
             Directive::Subtitle(text) => {
-                lines.push_subtitle(text, cmd_directory);
+                // subtitles replace the previous subtitle of the same source
+                *section = outer_section
+                    .iter()
+                    .cloned()
+                    .chain(::core::iter::once(Arc::from(text.as_ref())))
+                    .collect();
+                lines.context.section = section.clone();
+                *subtitle = Some(lines.lines.len());
+                lines.push_subtitle(text);
+            }
+            Directive::SectionExe(words) => {
+                if let Some(subtitle) = *subtitle {
+                    // the command of a subtitle executes all lines of its section
+                    let handle = lines.lines[subtitle].cmd_or_new(cmd_directory);
+                    let mut words = words.into_iter();
+                    let cmd = cmd_directory[handle]
+                        .exe(PathBuf::from(words.next().unwrap_or_default()))
+                        .default_cwd(|| dir.to_path_buf())
+                        .batch();
+                    for arg in words {
+                        cmd.arg(arg);
+                    }
+                } else {
+                    lines.push_warning(
+                        "section-exe needs to be placed somewhere after a subtitle directive"
+                            .into(),
+                    );
+                }
+            }
+            Directive::Tags(new_tags) => {
+                *tags = new_tags.iter().map(|tag| Arc::from(tag.as_ref())).collect();
             }
             Directive::Import(import) => {
-                match import.perform_import(
-                    shallow.shallow(),
-                    imported,
-                    cmd_directory,
-                    &provider,
-                    home,
-                ) {
+                match import.perform_import(shallow.shallow(), imported, cmd_directory, cache, home)
+                {
                     Ok(source) => {
                         return Ok(SourceAction::Push(source));
                     }
@@ -338,8 +430,8 @@ impl SourceAction {
                     }
                 }
             }
-            Directive::Empty => lines.push_empty(cmd_directory),
-            Directive::Text(text) => lines.push_line(text, cmd_directory),
+            Directive::Empty => lines.push_empty(),
+            Directive::Text(text) => lines.push_line(text),
 
             Directive::Multiple(parses) => {
                 for directive in parses.into_iter().rev() {
@@ -1,28 +1,14 @@
 use ::std::path::{Path, PathBuf};
 
 pub trait PathExt {
+    /// Canonicalize path as if dest were the current directory, without
+    /// changing the current directory of the process.
     fn canonicalize_at(&self, dest: &Self) -> ::std::io::Result<PathBuf>;
 }
 
 impl PathExt for Path {
     fn canonicalize_at(&self, dest: &Self) -> ::std::io::Result<PathBuf> {
-        fn internal(dest: &Path, path: &Path) -> ::std::io::Result<PathBuf> {
-            ::std::env::set_current_dir(dest)?;
-            path.canonicalize()
-        }
-
-        let s = ::std::env::current_dir().expect("should be able to get current directory");
-
-        let s = if s.is_absolute() {
-            s
-        } else {
-            s.canonicalize()
-                .expect("should be able to canonicalize current directory")
-        };
-
-        let r = internal(dest, self);
-        ::std::env::set_current_dir(s)
-            .expect("should be able to restore current directory to a prior value");
-        r
+        // absolute paths replace dest when joined
+        dest.join(self).canonicalize()
     }
 }
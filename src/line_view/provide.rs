@@ -1,9 +1,16 @@
+use ::std::{
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use ::flate2::bufread::MultiGzDecoder;
+
 use crate::line_view::Result;
 
 pub trait Read {
     type BufRead: ::std::io::BufRead + ::core::fmt::Debug + 'static;
 
-    fn provide(&self, from: &str) -> Result<Self::BufRead>;
+    fn provide(&self, from: &Path) -> Result<Self::BufRead>;
 }
 
 impl<P> self::Read for &P
@@ -12,7 +19,7 @@ where
 {
     type BufRead = P::BufRead;
 
-    fn provide(&self, from: &str) -> Result<Self::BufRead> {
+    fn provide(&self, from: &Path) -> Result<Self::BufRead> {
         (*self).provide(from)
     }
 }
@@ -22,7 +29,106 @@ pub struct PathReadProvider;
 impl self::Read for PathReadProvider {
     type BufRead = ::std::io::BufReader<std::fs::File>;
 
-    fn provide(&self, from: &str) -> Result<Self::BufRead> {
+    fn provide(&self, from: &Path) -> Result<Self::BufRead> {
         Ok(std::io::BufReader::new(std::fs::File::open(from)?))
     }
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+    const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+    /// Detect compression by magic bytes. Content without any is plain,
+    /// with a warning if the extension of path claims otherwise.
+    fn detect(path: &Path, head: &[u8]) -> Option<Self> {
+        if head.starts_with(Self::GZIP_MAGIC) {
+            Some(Self::Gzip)
+        } else if head.starts_with(Self::ZSTD_MAGIC) {
+            Some(Self::Zstd)
+        } else {
+            let expected = match path.extension()?.to_str()? {
+                "gz" => "gzip",
+                "zst" => "zstd",
+                _ => return None,
+            };
+            ::log::warn!("{path:?} is not {expected} compressed, reading it as plain text");
+            None
+        }
+    }
+}
+
+pub enum Decompressed<R: BufRead> {
+    Plain(R),
+    Gzip(BufReader<MultiGzDecoder<R>>),
+    Zstd(BufReader<::zstd::Decoder<'static, R>>),
+}
+
+impl<R: BufRead> ::core::fmt::Debug for Decompressed<R> {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        f.write_str(match self {
+            Self::Plain(..) => "Decompressed::Plain",
+            Self::Gzip(..) => "Decompressed::Gzip",
+            Self::Zstd(..) => "Decompressed::Zstd",
+        })
+    }
+}
+
+impl<R: BufRead> io::Read for Decompressed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(read) => read.read(buf),
+            Self::Gzip(read) => read.read(buf),
+            Self::Zstd(read) => read.read(buf),
+        }
+    }
+}
+
+impl<R: BufRead> BufRead for Decompressed<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Plain(read) => read.fill_buf(),
+            Self::Gzip(read) => read.fill_buf(),
+            Self::Zstd(read) => read.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amount: usize) {
+        match self {
+            Self::Plain(read) => read.consume(amount),
+            Self::Gzip(read) => read.consume(amount),
+            Self::Zstd(read) => read.consume(amount),
+        }
+    }
+}
+
+/// Wraps a provider, transparently decompressing gzip and zstd content.
+/// Paths are passed to the wrapped provider unchanged.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decompress<P>(pub P);
+
+impl<P> self::Read for Decompress<P>
+where
+    P: self::Read,
+{
+    type BufRead = Decompressed<P::BufRead>;
+
+    fn provide(&self, from: &Path) -> Result<Self::BufRead> {
+        let Self(provider) = self;
+        let mut read = provider.provide(from)?;
+        Ok(match Compression::detect(from, read.fill_buf()?) {
+            None => Decompressed::Plain(read),
+            Some(Compression::Gzip) => {
+                Decompressed::Gzip(BufReader::new(MultiGzDecoder::new(read)))
+            }
+            Some(Compression::Zstd) => {
+                Decompressed::Zstd(BufReader::new(::zstd::Decoder::with_buffer(read)?))
+            }
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use ::std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::line_view::{Error, Result};
+
+/// Paths needed by most programs to run, always readable in a sandbox.
+#[cfg(target_os = "linux")]
+const SYSTEM_PATHS: &[&str] = &[
+    "/usr", "/bin", "/sbin", "/lib", "/lib64", "/etc", "/proc", "/run",
+];
+
+/// Devices always writable in a sandbox.
+#[cfg(target_os = "linux")]
+const DEVICE_PATHS: &[&str] = &["/dev/null", "/dev/zero", "/dev/urandom", "/dev/tty"];
+
+/// Scratch directories always writable in a sandbox.
+#[cfg(target_os = "linux")]
+const TEMP_PATHS: &[&str] = &["/tmp"];
+
+/// Restrictions applied to spawned processes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Sandbox {
+    /// Paths that may be read and executed.
+    pub ro: Vec<PathBuf>,
+    /// Paths that may be read, written and executed.
+    pub rw: Vec<PathBuf>,
+    /// Run in a new network namespace without any network access.
+    pub no_net: bool,
+}
+
+impl Sandbox {
+    pub fn extend(&mut self, ro: Vec<PathBuf>, rw: Vec<PathBuf>, no_net: bool) {
+        self.ro.extend(ro);
+        self.rw.extend(rw);
+        self.no_net |= no_net;
+    }
+
+    /// Sandbox processes spawned by command, filesystem access is
+    /// restricted using landlock, which is required to be supported.
+    ///
+    /// The working directory, or that of the viewer if none is given, is
+    /// readable in addition to the paths of the sandbox.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self, command: &mut Command, cwd: Option<&Path>) -> Result<()> {
+        use ::std::{io, os::unix::process::CommandExt};
+
+        use ::landlock::{
+            ABI, Access, AccessFs, Ruleset, RulesetAttr, RulesetCreatedAttr, RulesetStatus,
+            path_beneath_rules,
+        };
+
+        let abi = ABI::V5;
+        let cwd = cwd
+            .map(Path::to_path_buf)
+            .or_else(|| ::std::env::current_dir().ok());
+        let mut ruleset = Some(
+            Ruleset::default()
+                .handle_access(AccessFs::from_all(abi))
+                .and_then(Ruleset::create)
+                .and_then(|ruleset| {
+                    ruleset.add_rules(path_beneath_rules(
+                        SYSTEM_PATHS
+                            .iter()
+                            .map(PathBuf::from)
+                            .chain(cwd)
+                            .chain(self.ro.clone()),
+                        AccessFs::from_read(abi),
+                    ))
+                })
+                .and_then(|ruleset| {
+                    ruleset.add_rules(path_beneath_rules(
+                        DEVICE_PATHS
+                            .iter()
+                            .chain(TEMP_PATHS)
+                            .map(PathBuf::from)
+                            .chain(self.rw.clone()),
+                        AccessFs::from_all(abi),
+                    ))
+                })
+                .map_err(|err| Error::Sandbox(err.to_string()))?,
+        );
+
+        // formatted before fork since allocating in the child is not safe
+        let id_maps = self.no_net.then(|| {
+            // SAFETY: getuid and getgid are always successful
+            let (uid, gid) = unsafe { (::libc::getuid(), ::libc::getgid()) };
+            (format!("{uid} {uid} 1"), format!("{gid} {gid} 1"))
+        });
+
+        let pre_exec = move || {
+            if let Some((uid_map, gid_map)) = &id_maps {
+                // SAFETY: unshare only affects the calling process
+                if unsafe { ::libc::unshare(::libc::CLONE_NEWUSER | ::libc::CLONE_NEWNET) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                write_proc(c"/proc/self/setgroups", b"deny")?;
+                write_proc(c"/proc/self/uid_map", uid_map.as_bytes())?;
+                write_proc(c"/proc/self/gid_map", gid_map.as_bytes())?;
+            }
+
+            let Some(ruleset) = ruleset.take() else {
+                return Err(io::Error::from_raw_os_error(::libc::EINVAL));
+            };
+            match ruleset.restrict_self() {
+                Ok(status) if status.ruleset != RulesetStatus::NotEnforced => Ok(()),
+                // landlock is not supported by kernel
+                Ok(..) => Err(io::Error::from_raw_os_error(::libc::ENOSYS)),
+                Err(..) => Err(io::Error::last_os_error()),
+            }
+        };
+
+        // SAFETY: the pre exec hook only performs system calls and does not
+        // allocate, which would not be safe in the forked child
+        unsafe { command.pre_exec(pre_exec) };
+        Ok(())
+    }
+
+    /// Sandboxing is only supported on linux.
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self, _command: &mut Command, _cwd: Option<&Path>) -> Result<()> {
+        Err(Error::Sandbox(
+            "sandboxing is only supported on linux".to_owned(),
+        ))
+    }
+}
+
+/// Write content to a file in procfs without allocating.
+#[cfg(target_os = "linux")]
+fn write_proc(path: &::core::ffi::CStr, content: &[u8]) -> ::std::io::Result<()> {
+    // SAFETY: path is a valid nul terminated string
+    let fd = unsafe { ::libc::open(path.as_ptr(), ::libc::O_WRONLY | ::libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(::std::io::Error::last_os_error());
+    }
+    // SAFETY: fd is open and content is valid for its length
+    let written = unsafe { ::libc::write(fd, content.as_ptr().cast(), content.len()) };
+    let err = ::std::io::Error::last_os_error();
+    // SAFETY: fd is open and not used after being closed
+    unsafe { ::libc::close(fd) };
+    if written < 0 { Err(err) } else { Ok(()) }
+}
@@ -42,6 +42,9 @@ fn main() -> ::color_eyre::Result<()> {
         Action::MimeType(mime_type) => mime_type.write(),
         Action::Application(application) => application.generate(),
         Action::Print(print) => print.print(),
+        Action::History(history) => history.print(),
+        Action::Config(config) => config.print(),
+        Action::Keys(keys) => keys.print(),
         Action::Open(open) => ui::run(open),
         Action::Daemon(daemon) => ui::run_daemon(daemon),
     }
@@ -0,0 +1,101 @@
+//! Store of files and directories whose commands may be used.
+
+use ::std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use ::serde::{Deserialize, Serialize};
+use ::sha2::{Digest, Sha256};
+
+use crate::config;
+
+/// Path of trust store.
+pub fn path() -> Option<PathBuf> {
+    config::dir().map(|dir| dir.join("trust.toml"))
+}
+
+/// Get sha-256 hash of file content as a hex string.
+///
+/// # Errors
+/// If the file cannot be read.
+pub fn hash(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Trusted files and directories.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Store {
+    /// Directories whose files, including those of subdirectories, are trusted.
+    pub directories: BTreeSet<PathBuf>,
+    /// Hashes of trusted file contents, see [hash].
+    pub files: BTreeSet<String>,
+}
+
+impl Store {
+    /// Read trust store, an empty store is used if it does not exist.
+    ///
+    /// # Errors
+    /// If the trust store cannot be read or parsed.
+    pub fn read() -> ::color_eyre::Result<Self> {
+        let Some(path) = path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(::toml::from_str(&content)?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Write trust store.
+    ///
+    /// # Errors
+    /// If the config directory cannot be found or the store cannot be written.
+    pub fn write(&self) -> ::color_eyre::Result<()> {
+        let path =
+            path().ok_or_else(|| ::color_eyre::eyre::eyre!("could not find config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, ::toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Check if file is trusted, either by being in a trusted directory or
+    /// by its content being trusted.
+    pub fn is_trusted(&self, path: &Path) -> bool {
+        let Ok(path) = path.canonicalize() else {
+            return false;
+        };
+        self.directories.iter().any(|dir| path.starts_with(dir))
+            || hash(&path).is_ok_and(|hash| self.files.contains(&hash))
+    }
+
+    /// Trust all files in directory.
+    ///
+    /// # Errors
+    /// If the directory cannot be canonicalized.
+    pub fn trust_directory(&mut self, dir: &Path) -> io::Result<()> {
+        self.directories.insert(dir.canonicalize()?);
+        Ok(())
+    }
+
+    /// Trust current content of file.
+    ///
+    /// # Errors
+    /// If the file cannot be read.
+    pub fn trust_file(&mut self, path: &Path) -> io::Result<()> {
+        self.files.insert(hash(path)?);
+        Ok(())
+    }
+}
@@ -1,21 +1,31 @@
 //! Ui implementation.
 
-use ::core::{cell::RefCell, fmt::Debug, ops::ControlFlow, time::Duration};
+mod diff;
+mod output;
+mod supervisor;
+
+use ::core::{
+    cell::RefCell,
+    fmt::Debug,
+    ops::ControlFlow,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 use ::std::{
-    borrow::Cow,
     collections::{BTreeMap, BTreeSet},
-    path::PathBuf,
+    path::{Path, PathBuf},
     rc::Rc,
     sync::{Arc, LazyLock},
+    time::Instant,
 };
 
 use ::clap::ValueEnum;
 use ::color_eyre::eyre::eyre;
 use ::derive_more::{Deref, DerefMut};
-use ::hashbrown::HashMap;
+use ::hashbrown::{HashMap, HashSet};
 use ::iced::{
     Alignment::Center,
-    Element, Font,
+    Border, Color, Element, Font,
     Length::Fill,
     Padding, Subscription, Task, Theme, font,
     keyboard::{Key, Modifiers},
@@ -33,13 +43,38 @@ use ::regex::RegexSet;
 use ::tap::Pipe;
 
 use crate::{
-    cli::{Daemon, Open},
+    cli::{Daemon, Disabled, Enabled, Open},
+    config::{Config, DefaultAction},
+    history,
+    keymap::{Action, Chord, Keymap, Lookup},
     line_view::{
-        self, LineView,
+        self, Cmd, Line, LineView, Policy,
         provide::{self, PathReadProvider},
     },
+    trust,
+    ui::{
+        diff::Diff,
+        output::OutputPane,
+        supervisor::{self, Batch, ProcessId, Supervisor},
+    },
 };
 
+/// Time during which repeated executions of the same lines are ignored.
+const DEBOUNCE: Duration = Duration::from_millis(400);
+
+/// Space between rows of line list.
+const ROW_SPACING: f32 = 2.0;
+
+/// Height of text relative to its size, same as the iced default.
+const LINE_HEIGHT: f32 = 1.3;
+
+/// Rows of line list created above and below what is in view, so that
+/// scrolling does not show missing rows before the list is updated.
+const OVERSCAN: usize = 16;
+
+/// Time lines added or changed by a reload are highlighted for.
+const HIGHLIGHT: Duration = Duration::from_millis(1500);
+
 /// Request a path be either opened or used ast the start
 /// of a file dialog.
 #[derive(Debug, Clone, ZeroCopySend)]
@@ -53,6 +88,8 @@ pub struct OpenRequest {
     home: Option<StaticPath<4096>>,
     /// Index of theme used.
     themeidx: usize,
+    /// If lines of opened file may not be executed.
+    no_exec: bool,
 }
 
 /// Create receiver for ipc.
@@ -72,11 +109,22 @@ fn ipc_receiver(
             .get(message.themeidx)
             .copied()
             .unwrap_or_default();
+        let no_exec = message.no_exec;
 
         tx.send(if open_at {
-            Message::DialogAt { path, home, theme }
+            Message::DialogAt {
+                path,
+                home,
+                theme,
+                no_exec,
+            }
         } else {
-            Message::OpenFile { path, home, theme }
+            Message::OpenFile {
+                path,
+                home,
+                theme,
+                no_exec,
+            }
         })?;
         Ok(())
     }
@@ -91,6 +139,10 @@ fn application<F>(
     is_daemon: bool,
     /// Handle of ipc subscriber.
     subscriber: Option<SubscriberHandle>,
+    /// User configuration.
+    config: Config,
+    /// Trusted files and directories.
+    trust: trust::Store,
     /// Message receiver.
     receiver: ::flume::Receiver<Message>,
     /// Message sender.
@@ -101,6 +153,10 @@ fn application<F>(
 where
     F: 'static + Fn() -> Task<Message>,
 {
+    let (keymap, errors) = Keymap::new(&config.keys);
+    for err in errors {
+        ::log::warn!("invalid key binding in config\n{err}");
+    }
     ::iced::daemon(
         move || {
             let sender = sender.clone();
@@ -120,6 +176,9 @@ where
                     subscriber_handle,
                     is_daemon,
                     watcher,
+                    config: config.clone(),
+                    trust: trust.clone(),
+                    keymap: keymap.clone(),
                     ..Default::default()
                 },
                 Task::batch([receive_message, task()]),
@@ -155,6 +214,8 @@ pub fn run_daemon(daemon: Daemon) -> ::color_eyre::Result<()> {
     application()
         .is_daemon(true)
         .subscriber(handle)
+        .config(Config::read()?)
+        .trust(trust::Store::read()?)
         .receiver(rx)
         .sender(tx)
         .task(Task::none)
@@ -171,10 +232,14 @@ pub fn run(open: Open) -> ::color_eyre::Result<()> {
         home,
         file,
         ipc,
+        no_exec,
     } = open;
 
+    let config = Config::read()?;
     let (tx, rx) = ::flume::bounded::<Message>(16);
-    let home = home.or_else(::std::env::home_dir);
+    let home = home.or_else(|| config.home()).or_else(::std::env::home_dir);
+    let theme = theme.or_else(|| config.theme()).unwrap_or_default();
+    let ipc = ipc.unwrap_or(if config.ipc { Enabled } else { Disabled });
     let cwd = ::std::env::current_dir()?;
 
     let subscriber_handle = if ipc.is_enabled() {
@@ -195,6 +260,7 @@ pub fn run(open: Open) -> ::color_eyre::Result<()> {
                         .copied()
                         .position(|variant| variant == theme)
                         .unwrap_or(usize::MAX),
+                    no_exec,
                 })
             })
             .receive(ipc_receiver(tx.clone()));
@@ -215,6 +281,8 @@ pub fn run(open: Open) -> ::color_eyre::Result<()> {
 
     application()
         .maybe_subscriber(subscriber_handle)
+        .config(config)
+        .trust(trust::Store::read()?)
         .receiver(rx)
         .sender(tx)
         .task(move || {
@@ -223,12 +291,14 @@ pub fn run(open: Open) -> ::color_eyre::Result<()> {
                     path,
                     home: home.clone(),
                     theme,
+                    no_exec,
                 }
             } else {
                 Message::DialogAt {
                     path: cwd.clone(),
                     home: home.clone(),
                     theme,
+                    no_exec,
                 }
             })
         })
@@ -245,14 +315,37 @@ pub enum Message {
         /// Content of window.
         window: Arc<Window>,
     },
-    /// Set content of a window.
+    /// Set content of a window once loaded.
     /// Unlike `AddWindow` will not add new entries to windows.
     SetWindow {
         /// Id of window.
         id: window::Id,
+        /// Load content is the result of.
+        generation: u64,
         /// Content of window.
         window: Arc<Window>,
     },
+    /// Lines were read by a load in progress.
+    LinesRead {
+        /// Id of window being loaded.
+        id: window::Id,
+        /// Load lines were read by.
+        generation: u64,
+        /// Amount of lines read so far.
+        count: usize,
+        /// Lines read since last message, without commands, only sent if
+        /// the load is previewed.
+        lines: Vec<Line<Arc<Cmd>>>,
+    },
+    /// Reload content of a window.
+    Reload(window::Id),
+    /// Stop highlighting lines changed by a reload.
+    Unhighlight {
+        /// Id of window.
+        id: window::Id,
+        /// Load lines were changed by.
+        generation: u64,
+    },
     /// Window was given focus.
     WindowFocused(window::Id),
     /// Close a window.
@@ -278,6 +371,42 @@ pub enum Message {
         /// Line number to execute.
         line: usize,
     },
+    /// Keyboard modifiers changed.
+    ModifiersChanged(Modifiers),
+    /// A key was pressed.
+    KeyPressed(Chord),
+    /// Select or deselect a line.
+    SelectLine {
+        /// Id of window of line.
+        id: window::Id,
+        /// Index of line.
+        idx: usize,
+        /// Select all lines between last selected line and line.
+        range: bool,
+    },
+    /// Select or deselect line at cursor of focused window, or hovered line
+    /// if there is no cursor.
+    SelectHovered,
+    /// Move cursor of focused window to next or previous visible line.
+    MoveCursor {
+        /// Move to next line, otherwise previous.
+        down: bool,
+        /// Extend selection to line.
+        extend: bool,
+    },
+    /// Select all visible lines of focused window.
+    SelectAll,
+    /// Deselect all lines of a window.
+    ClearSelection(window::Id),
+    /// Execute selected lines of a window, or of the focused window.
+    ExecSelected(Option<window::Id>),
+    /// Execute all lines of a section, using the section command if set.
+    ExecSection {
+        /// Id of window of section.
+        id: window::Id,
+        /// Index of title of section.
+        idx: usize,
+    },
     /// Open a file dialog at location.
     DialogAt {
         /// Path to open dialog at.
@@ -286,6 +415,8 @@ pub enum Message {
         home: Option<PathBuf>,
         /// Theme to use.
         theme: ThemeValueEnum,
+        /// If lines of opened file may not be executed.
+        no_exec: bool,
     },
     /// Open a line-viewer file.
     OpenFile {
@@ -295,6 +426,15 @@ pub enum Message {
         home: Option<PathBuf>,
         /// Theme to use.
         theme: ThemeValueEnum,
+        /// If lines of file may not be executed.
+        no_exec: bool,
+    },
+    /// Trust untrusted files of a window and reload it.
+    Trust {
+        /// Id of window.
+        id: window::Id,
+        /// Trust the directories of files instead of their content.
+        directories: bool,
     },
     /// Notify watcher event.
     Watcher(::notify::Event),
@@ -307,7 +447,7 @@ pub enum Message {
         /// Id of window of line.
         id: window::Id,
         /// Section to toggle.
-        section: Section<'static>,
+        section: Section,
     },
     /// Toggle all sections.
     ToggleAll,
@@ -315,6 +455,26 @@ pub enum Message {
     CollapseAll,
     /// Open all sections.
     UncollapseAll,
+    /// Line list of window was scrolled.
+    Scrolled {
+        /// Id of window.
+        id: window::Id,
+        /// Vertical offset of line list.
+        offset: f32,
+        /// Height of line list.
+        height: f32,
+    },
+    /// Window was resized.
+    Resized {
+        /// Id of window.
+        id: window::Id,
+        /// New height of window.
+        height: f32,
+    },
+    /// Scroll focused window to first line.
+    ScrollTop,
+    /// Scroll focused window to last line.
+    ScrollBottom,
     /// Should filter be shown/used.
     ToggleFilter,
     /// Update content of filer.
@@ -330,26 +490,183 @@ pub enum Message {
     FocusPrev,
     /// Disengage in focused window. (hide search)
     Disengage,
+    /// Reap exited processes.
+    Reap,
+    /// Kill a running process.
+    Kill(ProcessId),
+    /// Execute line awaiting confirmation.
+    ConfirmExec(window::Id),
+    /// Cancel execution of line awaiting confirmation.
+    CancelExec(window::Id),
+    /// Copy captured output of a window to clipboard.
+    CopyOutput(window::Id),
+    /// Close captured output pane of a window.
+    CloseOutput(window::Id),
+    /// Dismiss a notice shown in a window.
+    DismissNotice {
+        /// Id of window of notice.
+        id: window::Id,
+        /// Index of notice.
+        idx: usize,
+    },
+    /// Should history be shown.
+    ToggleHistory,
+    /// Execute a history entry again.
+    Rerun {
+        /// Id of window entry is executed from.
+        id: window::Id,
+        /// Index of history entry.
+        idx: usize,
+    },
+    /// History entry to execute again was resolved from its line.
+    Resolved {
+        /// Id of window entry is executed from.
+        id: window::Id,
+        /// Entry with its invocation, or why it could not be resolved.
+        entry: Result<Box<history::Entry>, String>,
+    },
+    /// Show line of a history entry.
+    JumpTo {
+        /// Id of window history is shown in.
+        id: window::Id,
+        /// Index of history entry.
+        idx: usize,
+    },
 }
 
 /// Window state.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Window {
     /// Theme in use.
     theme: Theme,
     /// Window Title.
     title: String,
+    /// Path of viewed file.
+    path: PathBuf,
     /// Window home.
     home: Option<PathBuf>,
+    /// If lines may not be executed.
+    no_exec: bool,
     /// Lines.
     content: Result<LineView, String>,
 }
 
+impl Window {
+    /// Create a window for file without any content.
+    fn new(path: PathBuf, home: Option<PathBuf>, theme: Theme, no_exec: bool) -> Self {
+        Self {
+            theme,
+            title: format!("Line Viewer: {}", path.display()),
+            path,
+            home,
+            no_exec,
+            content: Ok(LineView::default()),
+        }
+    }
+
+    /// Load window content from file, returning the window and the set of
+    /// paths read. The amount of lines read, and if previewing the lines,
+    /// are passed to on_lines as they are read, and [None] is returned if it
+    /// cancels loading.
+    fn load(
+        path: PathBuf,
+        home: Option<PathBuf>,
+        theme: Theme,
+        no_exec: bool,
+        policy: Policy,
+        preview: bool,
+        on_lines: impl FnMut(usize, Vec<Line<Arc<Cmd>>>) -> ControlFlow<()>,
+    ) -> Option<(Arc<Self>, BTreeSet<PathBuf>)> {
+        let provider = PathReadProviderWrapper::default();
+        let content = match LineView::read_path_incremental(
+            path.as_path().into(),
+            provide::Decompress(provider.clone()),
+            home.as_deref(),
+            policy,
+            preview,
+            on_lines,
+        ) {
+            Err(line_view::Error::Cancelled) => return None,
+            result => result.map_err(|err| err.to_string()),
+        };
+
+        Some((
+            Arc::new(Self {
+                content,
+                ..Self::new(path, home, theme, no_exec)
+            }),
+            provider.get_set(),
+        ))
+    }
+}
+
+/// Load of window content in progress.
+#[derive(Debug, Clone)]
+struct Loading {
+    /// Generation of load, messages of other loads are ignored.
+    generation: u64,
+    /// Set to stop loading.
+    cancel: Arc<AtomicBool>,
+    /// Amount of lines read so far.
+    lines: usize,
+    /// If lines are shown as they are read, which is done when there is no
+    /// previous content to show.
+    preview: bool,
+}
+
+impl Loading {
+    /// Stop loading.
+    fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Lines awaiting confirmation before being executed.
+#[derive(Debug, Clone)]
+struct Confirm {
+    /// Lines to execute, grouped by process.
+    batches: Vec<Batch>,
+    /// History entries to execute again.
+    reruns: Vec<history::Entry>,
+    /// Message to show.
+    message: String,
+    /// Command line that will be executed.
+    command: String,
+}
+
 /// Section of lines to store extra metadata for.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Section<'a> {
-    /// Section is bounded by a title.
-    Title(Cow<'a, str>),
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Section {
+    /// Section is bounded by the title at index, which is kept matched to
+    /// the same title across reloads.
+    Title(usize),
+}
+
+/// What was executed, repeats of which are debounced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Exec {
+    /// Line at index.
+    Line(usize),
+    /// Lines selected at indices.
+    Selected(BTreeSet<usize>),
+    /// Section of title at index.
+    Section(usize),
+    /// History entry at index.
+    Rerun(usize),
+}
+
+impl Exec {
+    /// Map line indices, lines that are gone are dropped.
+    fn map_lines(self, new_of: impl Fn(usize) -> Option<usize>) -> Option<Self> {
+        Some(match self {
+            Self::Line(idx) => Self::Line(new_of(idx)?),
+            Self::Selected(selected) => {
+                Self::Selected(selected.into_iter().filter_map(new_of).collect())
+            }
+            Self::Section(idx) => Self::Section(new_of(idx)?),
+            Self::Rerun(idx) => Self::Rerun(idx),
+        })
+    }
 }
 
 /// Section metadata.
@@ -369,7 +686,7 @@ struct WindowState {
     /// Dynamic window state.
     hovered: Option<usize>,
     /// Section Metadata.
-    metadata: HashMap<Section<'static>, Metadata>,
+    metadata: HashMap<Section, Metadata>,
     /// Should filter be used.
     use_filter: bool,
     /// Contents of filter.
@@ -380,6 +697,131 @@ struct WindowState {
     filter_id: widget::Id,
     /// Id of outer window container.
     container_id: widget::Id,
+    /// Id of line scrollable.
+    scroll_id: widget::Id,
+    /// Notices shown to user, such as failed executions.
+    notices: Vec<String>,
+    /// Output of last captured command.
+    output: Option<OutputPane>,
+    /// Lines awaiting confirmation.
+    confirm: Option<Confirm>,
+    /// Selected lines.
+    selected: BTreeSet<usize>,
+    /// Line selection ranges start at.
+    anchor: Option<usize>,
+    /// Line at keyboard cursor.
+    cursor: Option<usize>,
+    /// Last execution and when it happened.
+    last_exec: Option<(Exec, Instant)>,
+    /// Should history be shown.
+    show_history: bool,
+    /// Load in progress.
+    loading: Option<Loading>,
+    /// Indices of lines currently shown, those not in a collapsed section
+    /// or hidden by filter.
+    visible: Vec<usize>,
+    /// Where each row of visible ends in the line list.
+    row_ends: Vec<f32>,
+    /// Vertical offset of line list.
+    scroll_offset: f32,
+    /// Height of line list when last scrolled, until then height of window
+    /// which line list is never taller than.
+    height: f32,
+    /// Lines added or changed by last reload.
+    highlighted: HashSet<usize>,
+    /// Load highlighted lines were changed by.
+    highlighted_by: u64,
+}
+
+impl WindowState {
+    /// Check if section started by title at index is collapsed.
+    fn is_collapsed(&self, title: usize) -> bool {
+        self.metadata
+            .get(&Section::Title(title))
+            .is_some_and(|meta| meta.is_collapsed)
+    }
+
+    /// Update visible lines from line at index from and on, needed when
+    /// content, filter or collapsed sections change.
+    fn update_visible(&mut self, from: usize, font_size: u16) {
+        let keep = self.visible.partition_point(|idx| *idx < from);
+        self.visible.truncate(keep);
+        self.row_ends.truncate(keep);
+
+        let window = Arc::clone(&self.window);
+        let Ok(content) = &window.content else {
+            return;
+        };
+
+        let mut is_collapsed = content
+            .iter()
+            .take(from)
+            .rposition(Line::is_title)
+            .is_some_and(|title| self.is_collapsed(title));
+        let mut end = self.row_ends.last().copied().unwrap_or_default();
+        for (idx, line) in content.iter().enumerate().skip(from) {
+            if line.is_title() {
+                is_collapsed = self.is_collapsed(idx);
+            } else if is_collapsed
+                || (self.use_filter && !self.filter_re.matches(line.text()).matched_all())
+            {
+                continue;
+            }
+            end += row_height(line, font_size);
+            self.visible.push(idx);
+            self.row_ends.push(end);
+        }
+    }
+
+    /// Where row of line list starts.
+    fn row_start(&self, row: usize) -> f32 {
+        row.checked_sub(1)
+            .and_then(|row| self.row_ends.get(row))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Line at top of line list mapped through diff, with how far into its
+    /// row the list is scrolled. If the line is gone the next line still
+    /// present is used instead.
+    fn scroll_anchor(&self, diff: &Diff) -> Option<(usize, f32)> {
+        let row = self
+            .row_ends
+            .partition_point(|end| *end <= self.scroll_offset);
+        let mut rows = self.visible.get(row..)?.iter();
+        let first = *rows.next()?;
+        diff.new_of(first)
+            .map(|idx| (idx, self.scroll_offset - self.row_start(row)))
+            .or_else(|| rows.find_map(|idx| diff.new_of(*idx)).map(|idx| (idx, 0.0)))
+    }
+
+    /// Record execution, false if the same was executed within [`DEBOUNCE`]
+    /// and should be ignored, such as for double clicks.
+    fn debounce(&mut self, exec: Exec) -> bool {
+        let now = Instant::now();
+        if let Some((last, at)) = &self.last_exec
+            && *last == exec
+            && now.duration_since(*at) < DEBOUNCE
+        {
+            return false;
+        }
+        self.last_exec = Some((exec, now));
+        true
+    }
+
+    /// Check if line may be selected.
+    fn is_selectable(&self, idx: usize) -> bool {
+        self.content
+            .as_ref()
+            .ok()
+            .and_then(|content| content.get(idx))
+            .is_some_and(|line| {
+                !line.is_title()
+                    && !line.is_warning()
+                    && !line.text().is_empty()
+                    && line.has_command()
+            })
+    }
 }
 
 /// Wrap a [PathReadProvider] adding provided paths to set.
@@ -397,28 +839,48 @@ impl PathReadProviderWrapper {
 impl provide::Read for PathReadProviderWrapper {
     type BufRead = <PathReadProvider as provide::Read>::BufRead;
 
-    fn provide(&self, from: &str) -> line_view::Result<Self::BufRead> {
+    fn provide(&self, from: &Path) -> line_view::Result<Self::BufRead> {
         let Self(provider, path_set) = self;
         let reader = provider.provide(from)?;
-        path_set.borrow_mut().insert(PathBuf::from(from));
+        path_set.borrow_mut().insert(from.to_path_buf());
         Ok(reader)
     }
 }
 
-/// Styled title widet.
-fn title(title: &str, is_collapsed: bool, id: window::Id) -> Element<'_, Message> {
+/// Height of the row of line list showing line, including spacing.
+fn row_height(line: &Line<Arc<Cmd>>, font_size: u16) -> f32 {
+    let height = if line.is_title() {
+        f32::from(font_size + font_size / 3) * LINE_HEIGHT
+    } else if line.text().is_empty() {
+        5.0
+    } else {
+        f32::from(font_size) * LINE_HEIGHT
+    };
+    height + ROW_SPACING
+}
+
+/// Styled title widet, executes section instead of toggling it if
+/// control is held.
+fn title(
+    title: &str,
+    is_collapsed: bool,
+    id: window::Id,
+    idx: usize,
+    modifiers: Modifiers,
+    size: u16,
+) -> Element<'_, Message> {
     let font = Font {
         weight: font::Weight::ExtraBold,
         ..Default::default()
     };
     let indicator = widget::text(if is_collapsed { "+" } else { "-" })
-        .size(16)
+        .size(size)
         .width(10)
         .center()
         .font(font);
     let text = widget::text(title)
         .wrapping(widget::text::Wrapping::None)
-        .size(16)
+        .size(size)
         .font(font);
 
     widget::row![indicator, text]
@@ -428,35 +890,138 @@ fn title(title: &str, is_collapsed: bool, id: window::Id) -> Element<'_, Message
         .pipe(widget::button)
         .style(widget::button::text)
         .padding(0)
-        .on_press_with(move || Message::ToggleSection {
-            id,
-            section: title
-                .to_owned()
-                .pipe(Cow::<str>::Owned)
-                .pipe(Section::Title),
+        .on_press_with(move || {
+            if modifiers.control() {
+                Message::ExecSection { id, idx }
+            } else {
+                Message::ToggleSection {
+                    id,
+                    section: Section::Title(idx),
+                }
+            }
         })
         .pipe(Element::from)
 }
 
+/// Style of rows showing lines changed by a reload.
+fn highlight(theme: &Theme) -> widget::container::Style {
+    widget::container::Style {
+        background: Some(
+            theme
+                .extended_palette()
+                .primary
+                .weak
+                .color
+                .scale_alpha(0.4)
+                .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// Style of row showing line at keyboard cursor.
+fn cursor(theme: &Theme) -> widget::container::Style {
+    widget::container::Style {
+        border: Border {
+            color: theme.extended_palette().primary.base.color,
+            width: 1.0,
+            radius: 2.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Show dialog on top of base, blocking interaction with base.
+fn modal<'a>(
+    base: impl Into<Element<'a, Message>>,
+    dialog: impl Into<Element<'a, Message>>,
+    on_blur: Message,
+) -> Element<'a, Message> {
+    widget::stack![
+        base.into(),
+        widget::opaque(
+            widget::mouse_area(widget::center(widget::opaque(dialog)).style(|_theme| {
+                widget::container::Style {
+                    background: Some(
+                        Color {
+                            a: 0.8,
+                            ..Color::BLACK
+                        }
+                        .into(),
+                    ),
+                    ..Default::default()
+                }
+            }))
+            .on_press(on_blur)
+        )
+    ]
+    .into()
+}
+
+/// Dialog asking for confirmation before executing lines.
+fn confirm_dialog(id: window::Id, confirm: &Confirm) -> Element<'_, Message> {
+    widget::column![
+        widget::text(&confirm.message).size(16).font(Font {
+            weight: font::Weight::Bold,
+            ..Default::default()
+        }),
+        widget::text(&confirm.command)
+            .font(Font::MONOSPACE)
+            .size(12),
+        widget::row![
+            widget::button(widget::text("Yes").size(12))
+                .style(widget::button::danger)
+                .on_press(Message::ConfirmExec(id)),
+            widget::button(widget::text("No").size(12))
+                .style(widget::button::secondary)
+                .on_press(Message::CancelExec(id)),
+        ]
+        .spacing(5),
+    ]
+    .spacing(10)
+    .max_width(600)
+    .pipe(widget::container)
+    .padding(10)
+    .style(widget::container::bordered_box)
+    .into()
+}
+
+/// Convert a pressed key to a chord, modifier keys and keys without a name
+/// or character are ignored.
+fn chord(key: &Key, modifiers: Modifiers) -> Option<Chord> {
+    use ::iced::keyboard::key::Named;
+    let key = match key.as_ref() {
+        Key::Named(
+            Named::Shift
+            | Named::Control
+            | Named::Alt
+            | Named::AltGraph
+            | Named::Super
+            | Named::Meta
+            | Named::Hyper,
+        ) => return None,
+        Key::Named(named) => format!("{named:?}").to_lowercase(),
+        Key::Character(character) => character.to_owned(),
+        Key::Unidentified => return None,
+    };
+    Some(Chord {
+        ctrl: modifiers.control(),
+        alt: modifiers.alt(),
+        shift: modifiers.shift(),
+        logo: modifiers.logo(),
+        key,
+    })
+}
+
 /// Keyboard subscriptions.
 fn key_subscription() -> Subscription<Message> {
     ::iced::keyboard::listen().filter_map(|event| match event {
-        ::iced::keyboard::Event::KeyPressed { key, modifiers, .. } => match key.as_ref() {
-            Key::Named(::iced::keyboard::key::Named::Tab) if modifiers == Modifiers::NONE => {
-                Some(Message::FocusNext)
-            }
-            Key::Named(::iced::keyboard::key::Named::Tab) if modifiers == Modifiers::SHIFT => {
-                Some(Message::FocusPrev)
-            }
-            Key::Named(::iced::keyboard::key::Named::Escape) => Some(Message::Disengage),
-            Key::Character("q") if modifiers == Modifiers::CTRL => Some(Message::TryExit),
-            Key::Character("t") if modifiers == Modifiers::NONE => Some(Message::CollapseAll),
-            Key::Character("t") if modifiers == Modifiers::SHIFT => Some(Message::UncollapseAll),
-            Key::Character("+") if modifiers == Modifiers::NONE => Some(Message::UncollapseAll),
-            Key::Character("-") if modifiers == Modifiers::NONE => Some(Message::CollapseAll),
-            Key::Character("f") if modifiers == Modifiers::NONE => Some(Message::ToggleFilter),
-            _ => None,
-        },
+        ::iced::keyboard::Event::KeyPressed { key, modifiers, .. } => {
+            chord(&key, modifiers).map(Message::KeyPressed)
+        }
+        ::iced::keyboard::Event::ModifiersChanged(modifiers) => {
+            Some(Message::ModifiersChanged(modifiers))
+        }
         _ => None,
     })
 }
@@ -476,6 +1041,22 @@ struct State {
     watcher: Option<RecommendedWatcher>,
     /// Paths watched by windows.
     watched: BTreeMap<PathBuf, BTreeSet<window::Id>>,
+    /// Supervisor of executed lines.
+    supervisor: Supervisor,
+    /// Execution history, read when first shown.
+    history: Option<Vec<history::Entry>>,
+    /// Currently held keyboard modifiers.
+    modifiers: Modifiers,
+    /// User configuration.
+    config: Config,
+    /// Trusted files and directories.
+    trust: trust::Store,
+    /// Key bindings.
+    keymap: Keymap,
+    /// Chords pressed so far of a key sequence.
+    pending_keys: Vec<Chord>,
+    /// Amount of loads started, used as generation of the next one.
+    loads: u64,
 }
 
 impl State {
@@ -503,7 +1084,16 @@ impl State {
                     handle.and_then(|handle| handle.is_closed().then_some(Message::TryExit))
                 }),
             window::close_events().map(Message::Close),
+            window::resize_events().map(|(id, size)| Message::Resized {
+                id,
+                height: size.height,
+            }),
             key_subscription(),
+            if self.supervisor.is_idle() {
+                Subscription::none()
+            } else {
+                ::iced::time::every(Duration::from_millis(100)).map(|_| Message::Reap)
+            },
         ])
     }
 
@@ -523,6 +1113,268 @@ impl State {
         }
     }
 
+    /// Policy to load files with.
+    fn policy(&self, no_exec: bool) -> Policy {
+        self.config.policy(self.trust.clone(), no_exec)
+    }
+
+    /// Reload content of a window in the background, cancelling any load
+    /// already in progress. Lines are shown as they are read if the window
+    /// has no content, otherwise the current content is kept until loading
+    /// is done.
+    fn reload(&mut self, id: window::Id) -> Task<Message> {
+        let Some(no_exec) = self.windows.get(&id).map(|window| window.no_exec) else {
+            return Task::none();
+        };
+        let policy = self.policy(no_exec);
+        let Some(window) = self.windows.get_mut(&id) else {
+            return Task::none();
+        };
+
+        if let Some(loading) = window.loading.take() {
+            loading.cancel();
+        }
+
+        self.loads += 1;
+        let generation = self.loads;
+        let cancel = Arc::new(AtomicBool::new(false));
+        let preview = window.content.as_ref().map_or(true, LineView::is_empty);
+        if preview && window.content.is_err() {
+            Arc::make_mut(&mut window.window).content = Ok(LineView::default());
+        }
+        window.loading = Some(Loading {
+            generation,
+            cancel: Arc::clone(&cancel),
+            lines: 0,
+            preview,
+        });
+
+        let path = window.path.clone();
+        let home = window.home.clone();
+        let theme = window.theme.clone();
+        let (sender, receiver) = ::flume::unbounded();
+        let load = Task::future(::smol::unblock(move || {
+            let mut sent = 0;
+            Window::load(
+                path,
+                home,
+                theme,
+                no_exec,
+                policy,
+                preview,
+                |count, lines| {
+                    if cancel.load(Ordering::Relaxed) {
+                        return ControlFlow::Break(());
+                    }
+                    if count > sent {
+                        sent = count;
+                        _ = sender.send(Message::LinesRead {
+                            id,
+                            generation,
+                            count,
+                            lines,
+                        });
+                    }
+                    ControlFlow::Continue(())
+                },
+            )
+        }))
+        .then(move |loaded| {
+            let Some((window, path_set)) = loaded else {
+                return Task::none();
+            };
+            Task::done(Message::SetWindow {
+                id,
+                generation,
+                window,
+            })
+            .chain(Task::batch(
+                path_set
+                    .into_iter()
+                    .map(|path| Task::done(Message::Watch(path, id))),
+            ))
+        });
+
+        Task::batch([Task::stream(receiver.into_stream()), load])
+    }
+
+    /// Update visible lines of window after filter or collapsed sections
+    /// changed.
+    fn update_visible(&mut self, id: window::Id) {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.update_visible(0, self.config.font_size);
+        }
+    }
+
+    /// Record an execution in history.
+    fn record(&mut self, entry: history::Entry) {
+        if let Err(err) = history::append(&entry) {
+            ::log::error!("could not write history\n{err}");
+        }
+        if let Some(history) = &mut self.history {
+            history.push(entry);
+        }
+    }
+
+    /// Execute batches from window, asking for confirmation first if the
+    /// command of any batch requires it.
+    fn execute_batches(&mut self, id: window::Id, batches: Vec<Batch>) {
+        if let Some(message) = batches.iter().find_map(|batch| batch.cmd.get_confirm())
+            && let Some(window) = self.windows.get_mut(&id)
+        {
+            window.confirm = Some(Confirm {
+                message: message.to_owned(),
+                command: batches
+                    .iter()
+                    .filter_map(Batch::invocation)
+                    .map(|invocation| invocation.command_line())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                batches,
+                reruns: Vec::new(),
+            });
+        } else {
+            for batch in batches {
+                self.supervisor.execute_batch(id, batch);
+            }
+        }
+    }
+
+    /// Apply configured default action to a line without a command.
+    fn default_action(&mut self, id: window::Id, line: Line<Arc<Cmd>>) -> Task<Message> {
+        match self.config.default_action {
+            DefaultAction::None => Task::none(),
+            DefaultAction::Copy => ::iced::clipboard::write(line.text().to_owned()),
+            DefaultAction::Open => {
+                let policy = self.policy(false);
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                // lines whose commands were withheld are not opened instead
+                let is_untrusted = window.content.as_ref().is_ok_and(|content| {
+                    content
+                        .untrusted()
+                        .iter()
+                        .any(|path| line.source().path() == Some(&**path))
+                });
+                if window.no_exec || is_untrusted || line.cmd().is_blocked() {
+                    return Task::none();
+                }
+
+                let mut cmd = Cmd::default();
+                match self.config.opener(line.text()) {
+                    Some(opener) => {
+                        let mut command = opener.command.iter();
+                        if let Some(exe) = command.next() {
+                            cmd.exe(PathBuf::from(exe));
+                        }
+                        for arg in command {
+                            cmd.arg(arg.clone());
+                        }
+                    }
+                    None => {
+                        cmd.exe(PathBuf::from("xdg-open"));
+                    }
+                }
+                cmd.default_cwd(|| PathBuf::from(&*line.context().dir));
+
+                if let Some(program) = cmd.program()
+                    && !policy.allows(&program)
+                {
+                    window
+                        .notices
+                        .push(format!("opener {} is not allowed", program.display()));
+                    return Task::none();
+                }
+
+                self.supervisor.execute_batch(
+                    id,
+                    Batch {
+                        cmd: Arc::new(cmd),
+                        cmd_id: None,
+                        lines: vec![line],
+                    },
+                );
+                Task::none()
+            }
+        }
+    }
+
+    /// Open a new window showing window.
+    fn open(window: Window) -> Task<Message> {
+        let window = Arc::new(window);
+        let (id, task) = window::open(window::Settings::default());
+
+        task.map(move |id| {
+            let window = window.clone();
+            Message::AddWindow { id, window }
+        })
+        .chain(Task::done(Message::Reload(id)))
+    }
+
+    /// Show line of history entry, in the focused window if it contains the
+    /// line, otherwise in any window containing it. If no window contains it
+    /// the file of the line is opened like the window history is shown in.
+    fn jump_to(&mut self, from: window::Id, entry: &history::Entry) -> Task<Message> {
+        let find = |window: &WindowState| {
+            window
+                .content
+                .as_ref()
+                .ok()
+                .and_then(|content| content.iter().position(|line| entry.is_of(line)))
+        };
+
+        let found = self
+            .last_focused
+            .and_then(|id| Some((id, find(self.windows.get(&id)?)?)))
+            .or_else(|| {
+                self.windows
+                    .iter()
+                    .find_map(|(id, window)| Some((*id, find(window)?)))
+            });
+
+        let Some((id, idx)) = found else {
+            let (Some(path), Some(from)) = (&entry.source, self.windows.get(&from)) else {
+                return Task::none();
+            };
+            return Self::open(Window::new(
+                path.to_path(),
+                from.home.clone(),
+                from.theme.clone(),
+                from.no_exec,
+            ));
+        };
+        let Some(window) = self.windows.get_mut(&id) else {
+            return Task::none();
+        };
+
+        if let Ok(content) = &window.content
+            && let Some(title) = content.iter().take(idx).rposition(Line::is_title)
+        {
+            window
+                .metadata
+                .entry(Section::Title(title))
+                .or_default()
+                .is_collapsed = false;
+        }
+        window.hovered = Some(idx);
+        window.update_visible(0, self.config.font_size);
+
+        // a line hidden by filter is scrolled to where it would be
+        let row = window.visible.partition_point(|visible| *visible < idx);
+        window.scroll_offset = window.row_start(row);
+        Task::batch([
+            widget::operation::scroll_to(
+                window.scroll_id.clone(),
+                widget::operation::AbsoluteOffset {
+                    x: None,
+                    y: Some(window.scroll_offset),
+                },
+            ),
+            window::gain_focus(id),
+        ])
+    }
+
     /// Update ui state.
     pub fn update(&mut self, message: Message) -> Task<Message> {
         static RE_ALL: LazyLock<RegexSet> =
@@ -540,21 +1392,149 @@ impl State {
                         filter_re: RE_ALL.clone(),
                         filter_id: widget::Id::unique(),
                         container_id: widget::Id::unique(),
+                        notices: Vec::new(),
+                        output: None,
+                        confirm: None,
+                        selected: BTreeSet::new(),
+                        anchor: None,
+                        cursor: None,
+                        last_exec: None,
+                        show_history: false,
+                        scroll_id: widget::Id::unique(),
+                        loading: None,
+                        visible: Vec::new(),
+                        row_ends: Vec::new(),
+                        scroll_offset: 0.0,
+                        height: window::Settings::default().size.height,
+                        highlighted: HashSet::new(),
+                        highlighted_by: 0,
                     },
                 );
                 self.last_focused = Some(id);
                 Task::none()
             }
-            Message::SetWindow { id, window } => {
-                if let Some(entry) = self.windows.get_mut(&id) {
-                    entry.window = window;
-                } else {
+            Message::SetWindow {
+                id,
+                generation,
+                window,
+            } => {
+                let Some(entry) = self.windows.get_mut(&id) else {
                     ::log::warn!("could not set window content for id {id:?}");
+                    return Task::none();
+                };
+                // a later load has been started
+                if entry
+                    .loading
+                    .as_ref()
+                    .is_none_or(|loading| loading.generation != generation)
+                {
+                    return Task::none();
+                }
+                let preview = entry.loading.take().is_some_and(|loading| loading.preview);
+                let old = ::core::mem::replace(&mut entry.window, window);
+                entry.highlighted.clear();
+
+                let (Ok(old_content), Ok(content)) = (&old.content, &entry.window.content) else {
+                    entry.update_visible(0, self.config.font_size);
+                    // indices may refer to other lines
+                    entry.metadata.clear();
+                    entry.selected.clear();
+                    entry.anchor = None;
+                    entry.cursor = None;
+                    entry.hovered = None;
+                    entry.last_exec = None;
+                    return Task::none();
+                };
+
+                // line state follows lines to where they are in the new content
+                let diff = Diff::new(old_content, content);
+                let scroll_anchor = entry.scroll_anchor(&diff);
+                // sections of titles that are gone are forgotten
+                entry.metadata = entry
+                    .metadata
+                    .drain()
+                    .filter_map(|(Section::Title(idx), meta)| {
+                        Some((Section::Title(diff.new_of(idx)?), meta))
+                    })
+                    .collect();
+                entry.update_visible(0, self.config.font_size);
+                entry.hovered = entry.hovered.and_then(|idx| diff.new_of(idx));
+                entry.anchor = entry.anchor.and_then(|idx| diff.new_of(idx));
+                entry.cursor = entry.cursor.and_then(|idx| diff.new_of(idx));
+                entry.last_exec = entry
+                    .last_exec
+                    .take()
+                    .and_then(|(exec, at)| Some((exec.map_lines(|idx| diff.new_of(idx))?, at)));
+                let selected = entry
+                    .selected
+                    .iter()
+                    .filter_map(|idx| diff.new_of(*idx))
+                    .filter(|idx| entry.is_selectable(*idx))
+                    .collect();
+                entry.selected = selected;
+
+                let mut tasks = Vec::new();
+                if let Some((idx, into_row)) = scroll_anchor {
+                    let row = entry.visible.partition_point(|visible| *visible < idx);
+                    let into_row = if entry.visible.get(row) == Some(&idx) {
+                        into_row
+                    } else {
+                        0.0
+                    };
+                    entry.scroll_offset = entry.row_start(row) + into_row;
+                    tasks.push(widget::operation::scroll_to(
+                        entry.scroll_id.clone(),
+                        widget::operation::AbsoluteOffset {
+                            x: None,
+                            y: Some(entry.scroll_offset),
+                        },
+                    ));
+                }
+                // lines shown as they were read are all new
+                if !preview && !diff.changed().is_empty() {
+                    entry.highlighted.extend(diff.changed());
+                    entry.highlighted_by = generation;
+                    tasks.push(
+                        Task::future(::smol::Timer::after(HIGHLIGHT))
+                            .map(move |_| Message::Unhighlight { id, generation }),
+                    );
+                }
+                Task::batch(tasks)
+            }
+            Message::LinesRead {
+                id,
+                generation,
+                count,
+                lines,
+            } => {
+                if let Some(entry) = self.windows.get_mut(&id)
+                    && let Some(loading) = &mut entry.loading
+                    && loading.generation == generation
+                {
+                    loading.lines = count;
+                    if !lines.is_empty()
+                        && let Ok(content) = &mut Arc::make_mut(&mut entry.window).content
+                    {
+                        let from = content.len();
+                        content.extend(lines);
+                        entry.update_visible(from, self.config.font_size);
+                    }
+                }
+                Task::none()
+            }
+            Message::Reload(id) => self.reload(id),
+            Message::Unhighlight { id, generation } => {
+                if let Some(window) = self.windows.get_mut(&id)
+                    && window.highlighted_by == generation
+                {
+                    window.highlighted.clear();
                 }
                 Task::none()
             }
             Message::Close(id) => {
-                self.windows.remove(&id);
+                if let Some(loading) = self.windows.remove(&id).and_then(|window| window.loading) {
+                    loading.cancel();
+                }
 
                 let unwatch = self.watched.extract_if(.., |_path, id_set| {
                     id_set.remove(&id);
@@ -590,77 +1570,544 @@ impl State {
                 }
                 Task::none()
             }
-            Message::ExecLine { id, line } => self
-                .windows
-                .get(&id)
-                .and_then(|window| window.content.as_ref().ok()?.get(line)?.clone().pipe(Some))
-                .map(|line| {
-                    Task::future(::smol::unblock(move || {
-                        if let Err(err) = line.execute() {
-                            ::log::error!("could not execute line\n{err}")
-                        }
-                    }))
-                    .discard()
-                })
-                .unwrap_or_else(Task::none),
-            Message::DialogAt { path, home, theme } => Task::future(async move {
-                let path = ::rfd::AsyncFileDialog::new()
-                    .set_title("Open Line View File")
-                    .set_directory(path)
-                    .pick_file()
-                    .await?
-                    .path()
-                    .to_path_buf();
+            Message::ExecLine { id, line } => {
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
 
-                Some(Message::OpenFile { path, home, theme })
-            })
-            .then(|message| message.map_or_else(Task::none, Task::done)),
-            Message::OpenFile { path, home, theme } => {
-                let Some(file) = path.to_str() else {
-                    ::log::error!("path {path:?} is not valid utf-8");
+                // commands of lines are not known until loading is done
+                if window
+                    .loading
+                    .as_ref()
+                    .is_some_and(|loading| loading.preview)
+                {
+                    return Task::none();
+                }
+
+                if !window.debounce(Exec::Line(line)) {
+                    return Task::none();
+                }
+
+                let Some(line) = window
+                    .content
+                    .as_ref()
+                    .ok()
+                    .and_then(|content| content.get(line))
+                    .cloned()
+                else {
                     return Task::none();
                 };
-                let file = file.to_owned();
-                Task::future(::smol::unblock(move || {
-                    let provider = PathReadProviderWrapper::default();
-                    let title = format!("Line Viewer: {file}");
-                    let theme = theme.into_inner();
-                    let content =
-                        LineView::read_path(file.into(), provider.clone(), home.as_deref())
-                            .map_err(|err| err.to_string());
-
-                    (
-                        Arc::new(Window {
-                            title,
-                            home,
-                            theme,
-                            content,
-                        }),
-                        provider.get_set(),
-                    )
-                }))
-                .then(move |(window, path_set)| {
-                    let (id, task) = window::open(window::Settings::default());
 
-                    task.map(move |id| {
-                        let window = window.clone();
-                        Message::AddWindow { id, window }
-                    })
-                    .chain(Task::batch(
-                        path_set
-                            .into_iter()
-                            .map(|path| Task::done(Message::Watch(path, id))),
-                    ))
-                })
+                if line.cmd().is_empty() {
+                    return self.default_action(id, line);
+                }
+
+                if let Some(message) = line.cmd().get_confirm() {
+                    window.confirm = Some(Confirm {
+                        message: message.to_owned(),
+                        command: line
+                            .invocation()
+                            .map(|invocation| invocation.command_line())
+                            .unwrap_or_default(),
+                        batches: vec![Batch::single(line)],
+                        reruns: Vec::new(),
+                    });
+                } else {
+                    self.supervisor.execute(id, line);
+                }
+                Task::none()
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers;
+                Task::none()
+            }
+            Message::KeyPressed(chord) => {
+                self.pending_keys.push(chord);
+                let mut lookup = self.keymap.lookup(&self.pending_keys);
+                // a chord breaking a sequence may start a new one
+                if lookup == Lookup::Unbound && self.pending_keys.len() > 1 {
+                    self.pending_keys.drain(..self.pending_keys.len() - 1);
+                    lookup = self.keymap.lookup(&self.pending_keys);
+                }
+                let action = match lookup {
+                    Lookup::Action(action) => action,
+                    Lookup::Prefix => return Task::none(),
+                    Lookup::Unbound => {
+                        self.pending_keys.clear();
+                        return Task::none();
+                    }
+                };
+                self.pending_keys.clear();
+                Task::done(match action {
+                    Action::FocusNext => Message::FocusNext,
+                    Action::FocusPrev => Message::FocusPrev,
+                    Action::Disengage => Message::Disengage,
+                    Action::Quit => Message::TryExit,
+                    Action::CollapseAll => Message::CollapseAll,
+                    Action::UncollapseAll => Message::UncollapseAll,
+                    Action::ToggleFilter => Message::ToggleFilter,
+                    Action::ToggleHistory => Message::ToggleHistory,
+                    Action::SelectHovered => Message::SelectHovered,
+                    Action::SelectAll => Message::SelectAll,
+                    Action::ExecSelected => Message::ExecSelected(None),
+                    Action::ScrollTop => Message::ScrollTop,
+                    Action::ScrollBottom => Message::ScrollBottom,
+                    Action::CursorDown => Message::MoveCursor {
+                        down: true,
+                        extend: false,
+                    },
+                    Action::CursorUp => Message::MoveCursor {
+                        down: false,
+                        extend: false,
+                    },
+                    Action::ExtendDown => Message::MoveCursor {
+                        down: true,
+                        extend: true,
+                    },
+                    Action::ExtendUp => Message::MoveCursor {
+                        down: false,
+                        extend: true,
+                    },
+                })
+            }
+            Message::SelectLine { id, idx, range } => {
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                window.cursor = Some(idx);
+                match window.anchor {
+                    Some(anchor) if range => {
+                        let (start, end) = (anchor.min(idx), anchor.max(idx));
+                        let selected = window
+                            .visible
+                            .iter()
+                            .copied()
+                            .filter(|idx| (start..=end).contains(idx))
+                            .filter(|idx| window.is_selectable(*idx))
+                            .collect::<Vec<_>>();
+                        window.selected.extend(selected);
+                    }
+                    _ => {
+                        if !window.selected.remove(&idx) && window.is_selectable(idx) {
+                            window.selected.insert(idx);
+                        }
+                        window.anchor = Some(idx);
+                    }
+                }
+                Task::none()
+            }
+            Message::SelectHovered => {
+                if let Some(focused) = self.last_focused
+                    && let Some(idx) = self
+                        .windows
+                        .get(&focused)
+                        .and_then(|window| window.cursor.or(window.hovered))
+                {
+                    self.update(Message::SelectLine {
+                        id: focused,
+                        idx,
+                        range: false,
+                    })
+                } else {
+                    Task::none()
+                }
+            }
+            Message::MoveCursor { down, extend } => {
+                let Some(id) = self.last_focused else {
+                    return Task::none();
+                };
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                let Some(last) = window.visible.len().checked_sub(1) else {
+                    return Task::none();
+                };
+
+                // without a cursor the first line in view is used, and a
+                // cursor on a line hidden since moves to the lines around it
+                let row = match window.cursor {
+                    Some(cursor) => {
+                        let row = window.visible.partition_point(|idx| *idx < cursor);
+                        if !down {
+                            row.saturating_sub(1)
+                        } else if window.visible.get(row) == Some(&cursor) {
+                            row + 1
+                        } else {
+                            row
+                        }
+                    }
+                    None => window
+                        .row_ends
+                        .partition_point(|end| *end <= window.scroll_offset),
+                }
+                .min(last);
+                let idx = window.visible[row];
+                let previous = window.cursor.replace(idx);
+
+                // keep line at cursor in view
+                let (start, end) = (window.row_start(row), window.row_ends[row]);
+                let scroll = if start < window.scroll_offset {
+                    Some(start)
+                } else if end > window.scroll_offset + window.height {
+                    Some(end - window.height)
+                } else {
+                    None
+                }
+                .map_or_else(Task::none, |offset| {
+                    window.scroll_offset = offset;
+                    widget::operation::scroll_to(
+                        window.scroll_id.clone(),
+                        widget::operation::AbsoluteOffset {
+                            x: None,
+                            y: Some(offset),
+                        },
+                    )
+                });
+
+                if !extend {
+                    return scroll;
+                }
+                // ranges extended without an anchor start at the previous line
+                if window.anchor.is_none() {
+                    window.anchor = previous;
+                }
+                Task::batch([
+                    scroll,
+                    self.update(Message::SelectLine {
+                        id,
+                        idx,
+                        range: true,
+                    }),
+                ])
+            }
+            Message::SelectAll => {
+                if let Some(focused) = self.last_focused
+                    && let Some(window) = self.windows.get_mut(&focused)
+                {
+                    let selected = window
+                        .visible
+                        .iter()
+                        .copied()
+                        .filter(|idx| window.is_selectable(*idx))
+                        .collect::<Vec<_>>();
+                    window.selected.extend(selected);
+                }
+                Task::none()
+            }
+            Message::ClearSelection(id) => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.selected.clear();
+                    window.anchor = None;
+                }
+                Task::none()
+            }
+            Message::ExecSelected(id) => {
+                let Some(id) = id.or(self.last_focused) else {
+                    return Task::none();
+                };
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                let selected = ::core::mem::take(&mut window.selected);
+                window.anchor = None;
+                if !window.debounce(Exec::Selected(selected.clone())) {
+                    return Task::none();
+                }
+                let Ok(content) = &window.content else {
+                    return Task::none();
+                };
+                let batches = Supervisor::batches(
+                    selected
+                        .into_iter()
+                        .filter_map(|idx| content.get(idx))
+                        .cloned(),
+                );
+                self.execute_batches(id, batches);
+                Task::none()
+            }
+            Message::ExecSection { id, idx } => {
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                if !window.debounce(Exec::Section(idx)) {
+                    return Task::none();
+                }
+                let Ok(content) = &window.content else {
+                    return Task::none();
+                };
+                let Some(title) = content.get(idx) else {
+                    return Task::none();
+                };
+                let batches = if title.has_command() {
+                    let lines = content.section(idx).cloned().collect::<Vec<_>>();
+                    if lines.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![Batch {
+                            cmd: Arc::clone(title.cmd()),
+                            cmd_id: title.cmd_id(),
+                            lines,
+                        }]
+                    }
+                } else {
+                    Supervisor::batches(
+                        content
+                            .section(idx)
+                            .filter(|line| line.has_command())
+                            .cloned(),
+                    )
+                };
+                self.execute_batches(id, batches);
+                Task::none()
+            }
+            Message::ConfirmExec(id) => {
+                if let Some(window) = self.windows.get_mut(&id)
+                    && let Some(Confirm {
+                        batches, reruns, ..
+                    }) = window.confirm.take()
+                {
+                    for batch in batches {
+                        self.supervisor.execute_batch(id, batch);
+                    }
+                    for entry in &reruns {
+                        self.supervisor.rerun(id, entry);
+                    }
+                }
+                Task::none()
+            }
+            Message::CancelExec(id) => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.confirm = None;
+                }
+                Task::none()
+            }
+            Message::Reap => {
+                for event in self.supervisor.reap() {
+                    match event {
+                        supervisor::Event::Captured {
+                            process,
+                            window,
+                            description,
+                        } => {
+                            if let Some(window) = self.windows.get_mut(&window) {
+                                window.output = Some(OutputPane::new(process, description));
+                            }
+                        }
+                        supervisor::Event::Output {
+                            process,
+                            window,
+                            line,
+                        } => {
+                            if let Some(window) = self.windows.get_mut(&window)
+                                && let Some(output) = &mut window.output
+                                && output.process == process
+                            {
+                                output.push(line);
+                            }
+                        }
+                        supervisor::Event::Exit(exit) => {
+                            self.record(exit.entry.clone());
+                            let Some(window) = self.windows.get_mut(&exit.window) else {
+                                continue;
+                            };
+                            if let Some(output) = &mut window.output
+                                && output.process == exit.process
+                            {
+                                output.status = Some(match &exit.kind {
+                                    supervisor::ExitKind::Exited(status) => status.to_string(),
+                                    supervisor::ExitKind::TimedOut(..) => "timed out".to_owned(),
+                                    supervisor::ExitKind::Killed => "killed".to_owned(),
+                                    supervisor::ExitKind::Failed(err) => err.clone(),
+                                });
+                            }
+                            if exit.is_failure() {
+                                ::log::error!("{exit}");
+                                window.notices.push(exit.to_string());
+                            } else {
+                                ::log::info!("{exit}");
+                            }
+                        }
+                    }
+                }
+                Task::none()
+            }
+            Message::CopyOutput(id) => self
+                .windows
+                .get(&id)
+                .and_then(|window| window.output.as_ref())
+                .map_or_else(Task::none, |output| {
+                    ::iced::clipboard::write(output.contents())
+                }),
+            Message::CloseOutput(id) => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.output = None;
+                }
+                Task::none()
+            }
+            Message::Kill(process) => {
+                self.supervisor.kill(process);
+                Task::none()
+            }
+            Message::DismissNotice { id, idx } => {
+                if let Some(window) = self.windows.get_mut(&id)
+                    && idx < window.notices.len()
+                {
+                    window.notices.remove(idx);
+                }
+                Task::none()
+            }
+            Message::ToggleHistory => {
+                if let Some(focused) = self.last_focused
+                    && let Some(window) = self.windows.get_mut(&focused)
+                {
+                    window.show_history = !window.show_history;
+                    if window.show_history && self.history.is_none() {
+                        self.history = Some(history::read().unwrap_or_else(|err| {
+                            ::log::error!("could not read history\n{err}");
+                            Vec::new()
+                        }));
+                    }
+                }
+                Task::none()
+            }
+            Message::Rerun { id, idx } => {
+                let Some(entry) = self
+                    .history
+                    .as_ref()
+                    .and_then(|history| history.get(idx))
+                    .cloned()
+                else {
+                    return Task::none();
+                };
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                if !window.debounce(Exec::Rerun(idx)) {
+                    return Task::none();
+                }
+                let home = window.home.clone();
+                if entry.invocation.is_some() {
+                    return self.update(Message::Resolved {
+                        id,
+                        entry: Ok(Box::new(entry)),
+                    });
+                }
+
+                // entries read from history are resolved from their line
+                // again since not everything needed to run them is stored
+                let policy = self.policy(false);
+                Task::future(::smol::unblock(move || {
+                    let mut entry = entry;
+                    let entry = match entry.resolve(home.as_deref(), policy) {
+                        Ok(invocation) => {
+                            entry.invocation = Some(invocation);
+                            Ok(Box::new(entry))
+                        }
+                        Err(err) => Err(format!("could not run {} again, {err}", entry.text)),
+                    };
+                    Message::Resolved { id, entry }
+                }))
+            }
+            Message::Resolved { id, entry } => {
+                let policy = self.policy(false);
+                let Some(window) = self.windows.get_mut(&id) else {
+                    return Task::none();
+                };
+                let entry = match entry {
+                    Ok(entry) => *entry,
+                    Err(err) => {
+                        window.notices.push(err);
+                        return Task::none();
+                    }
+                };
+                let Some(invocation) = &entry.invocation else {
+                    return Task::none();
+                };
+
+                // reruns follow the same rules as executing the line
+                let program = &invocation.program;
+                if window.no_exec {
+                    return Task::none();
+                }
+                if !policy.allows_invocation(invocation) {
+                    window
+                        .notices
+                        .push(format!("{} is not allowed", program.display()));
+                    return Task::none();
+                }
+
+                if let Some(message) = &entry.confirm {
+                    window.confirm = Some(Confirm {
+                        message: message.clone(),
+                        command: invocation.command_line(),
+                        batches: Vec::new(),
+                        reruns: vec![entry],
+                    });
+                } else {
+                    self.supervisor.rerun(id, &entry);
+                }
+                Task::none()
+            }
+            Message::JumpTo { id, idx } => self
+                .history
+                .as_ref()
+                .and_then(|history| history.get(idx))
+                .cloned()
+                .map_or_else(Task::none, |entry| self.jump_to(id, &entry)),
+            Message::DialogAt {
+                path,
+                home,
+                theme,
+                no_exec,
+            } => Task::future(async move {
+                let path = ::rfd::AsyncFileDialog::new()
+                    .set_title("Open Line View File")
+                    .set_directory(path)
+                    .pick_file()
+                    .await?
+                    .path()
+                    .to_path_buf();
+
+                Some(Message::OpenFile {
+                    path,
+                    home,
+                    theme,
+                    no_exec,
+                })
+            })
+            .then(|message| message.map_or_else(Task::none, Task::done)),
+            Message::OpenFile {
+                path,
+                home,
+                theme,
+                no_exec,
+            } => Self::open(Window::new(path, home, theme.into_inner(), no_exec)),
+            Message::Trust { id, directories } => {
+                let Some(content) = self
+                    .windows
+                    .get(&id)
+                    .and_then(|window| window.content.as_ref().ok())
+                else {
+                    return Task::none();
+                };
+                for path in content.untrusted() {
+                    let result = match path.parent() {
+                        Some(dir) if directories => self.trust.trust_directory(dir),
+                        _ => self.trust.trust_file(path),
+                    };
+                    if let Err(err) = result {
+                        ::log::error!("could not trust {path:?}\n{err}");
+                    }
+                }
+                if let Err(err) = self.trust.write() {
+                    ::log::error!("could not write trust store\n{err}");
+                }
+                self.reload(id)
             }
             Message::Watcher(event) => match event.kind {
                 EventKind::Create(CreateKind::File) | EventKind::Modify(ModifyKind::Data(..)) => {
                     let mut tasks = Vec::new();
                     for path in event.paths {
-                        let Some(file) = path.to_str() else {
-                            ::log::error!("path {path:?} is not valid utf-8");
-                            continue;
-                        };
                         let Some(id_set) = self.watched.get(&path) else {
                             if let Some(watcher) = &mut self.watcher
                                 && let Err(err) = watcher.unwatch(&path)
@@ -669,49 +2116,9 @@ impl State {
                             };
                             continue;
                         };
-                        for id in id_set {
-                            let Some(window) = self.windows.get(id) else {
-                                continue;
-                            };
-                            let file = file.to_owned();
-                            let theme = window.theme.clone();
-                            let home = window.home.clone();
-                            let id = *id;
-                            tasks.push(
-                                Task::future(::smol::unblock(move || {
-                                    let title = format!("Line Viewer: {file}");
-                                    let provider = PathReadProviderWrapper::default();
-                                    let theme = theme;
-                                    let content = LineView::read_path(
-                                        file.into(),
-                                        provider.clone(),
-                                        home.as_deref(),
-                                    )
-                                    .map_err(|err| err.to_string());
-
-                                    (
-                                        id,
-                                        Arc::new(Window {
-                                            title,
-                                            home,
-                                            theme,
-                                            content,
-                                        }),
-                                        provider.get_set(),
-                                    )
-                                }))
-                                .then(
-                                    |(id, window, path_set)| {
-                                        Task::done(Message::SetWindow { id, window }).chain(
-                                            Task::batch(
-                                                path_set.into_iter().map(|path| {
-                                                    Task::done(Message::Watch(path, id))
-                                                }),
-                                            ),
-                                        )
-                                    },
-                                ),
-                            );
+                        // loads in progress are cancelled and restarted
+                        for id in id_set.iter().copied().collect::<Vec<_>>() {
+                            tasks.push(self.reload(id));
                         }
                     }
                     Task::batch(tasks)
@@ -735,6 +2142,7 @@ impl State {
                 if let Some(window) = self.windows.get_mut(&id) {
                     let meta = window.metadata.entry(section).or_default();
                     meta.is_collapsed = !meta.is_collapsed;
+                    window.update_visible(0, self.config.font_size);
                 }
                 Task::none()
             }
@@ -745,19 +2153,16 @@ impl State {
                     }) = self.windows.get_mut(&focused)
                     && let Ok(content) = &window.content
                 {
-                    for title in content.iter().filter(|line| line.is_title()) {
-                        let entry = metadata
-                            .entry(
-                                title
-                                    .text()
-                                    .to_owned()
-                                    .pipe(Cow::<str>::Owned)
-                                    .pipe(Section::Title),
-                            )
-                            .or_default();
+                    for (idx, _) in content
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| line.is_title())
+                    {
+                        let entry = metadata.entry(Section::Title(idx)).or_default();
 
                         entry.is_collapsed = !entry.is_collapsed;
                     }
+                    self.update_visible(focused);
                 }
                 Task::none()
             }
@@ -768,19 +2173,16 @@ impl State {
                     }) = self.windows.get_mut(&focused)
                     && let Ok(content) = &window.content
                 {
-                    for title in content.iter().filter(|line| line.is_title()) {
-                        let entry = metadata
-                            .entry(
-                                title
-                                    .text()
-                                    .to_owned()
-                                    .pipe(Cow::<str>::Owned)
-                                    .pipe(Section::Title),
-                            )
-                            .or_default();
+                    for (idx, _) in content
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| line.is_title())
+                    {
+                        let entry = metadata.entry(Section::Title(idx)).or_default();
 
                         entry.is_collapsed = true;
                     }
+                    self.update_visible(focused);
                 }
                 Task::none()
             }
@@ -791,19 +2193,16 @@ impl State {
                     }) = self.windows.get_mut(&focused)
                     && let Ok(content) = &window.content
                 {
-                    for title in content.iter().filter(|line| line.is_title()) {
-                        let entry = metadata
-                            .entry(
-                                title
-                                    .text()
-                                    .to_owned()
-                                    .pipe(Cow::<str>::Owned)
-                                    .pipe(Section::Title),
-                            )
-                            .or_default();
+                    for (idx, _) in content
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, line)| line.is_title())
+                    {
+                        let entry = metadata.entry(Section::Title(idx)).or_default();
 
                         entry.is_collapsed = false;
                     }
+                    self.update_visible(focused);
                 }
                 Task::none()
             }
@@ -817,12 +2216,13 @@ impl State {
                     }) = self.windows.get_mut(&focused)
                 {
                     *use_filter = !*use_filter;
-
-                    widget::operation::focus(if *use_filter {
+                    let focus = if *use_filter {
                         filter_id.clone()
                     } else {
                         container_id.clone()
-                    })
+                    };
+                    self.update_visible(focused);
+                    widget::operation::focus(focus)
                 } else {
                     Task::none()
                 }
@@ -832,11 +2232,21 @@ impl State {
                     && let Some(WindowState {
                         use_filter,
                         container_id,
+                        confirm,
+                        selected,
+                        anchor,
+                        cursor,
                         ..
                     }) = self.windows.get_mut(&focused)
                 {
                     *use_filter = false;
-                    widget::operation::focus(container_id.clone())
+                    *confirm = None;
+                    selected.clear();
+                    *anchor = None;
+                    *cursor = None;
+                    let focus = container_id.clone();
+                    self.update_visible(focused);
+                    widget::operation::focus(focus)
                 } else {
                     Task::none()
                 }
@@ -854,31 +2264,446 @@ impl State {
                         *filter_re = new_re;
                     }
                     *filter = content;
+                    self.update_visible(id);
+                }
+                Task::none()
+            }
+            Message::Scrolled { id, offset, height } => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.scroll_offset = offset;
+                    window.height = height;
                 }
                 Task::none()
             }
+            Message::Resized { id, height } => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.height = height;
+                }
+                Task::none()
+            }
+            Message::ScrollTop | Message::ScrollBottom => {
+                let Some(window) = self.last_focused.and_then(|id| self.windows.get(&id)) else {
+                    return Task::none();
+                };
+                let y = if matches!(message, Message::ScrollTop) {
+                    0.0
+                } else {
+                    1.0
+                };
+                widget::operation::snap_to(
+                    window.scroll_id.clone(),
+                    widget::operation::RelativeOffset { x: 0.0, y },
+                )
+            }
             Message::FocusNext => widget::operation::focus_next(),
             Message::FocusPrev => widget::operation::focus_previous(),
         }
     }
 
+    /// Status of processes and notices for a window.
+    fn status<'this>(
+        &'this self,
+        id: window::Id,
+        notices: &'this [String],
+    ) -> Element<'this, Message> {
+        let running = self.supervisor.running(id).map(|(process, description)| {
+            widget::row![
+                widget::text(description)
+                    .wrapping(widget::text::Wrapping::None)
+                    .size(12)
+                    .width(Fill),
+                widget::button(widget::text("Kill").size(12))
+                    .padding([0, 5])
+                    .style(widget::button::danger)
+                    .on_press(Message::Kill(process)),
+            ]
+            .spacing(5)
+            .align_y(Center)
+            .pipe(Element::from)
+        });
+        let notices = notices.iter().enumerate().map(|(idx, notice)| {
+            widget::row![
+                widget::text(notice)
+                    .wrapping(widget::text::Wrapping::None)
+                    .style(widget::text::danger)
+                    .size(12)
+                    .width(Fill),
+                widget::button(widget::text("Dismiss").size(12))
+                    .padding([0, 5])
+                    .style(widget::button::secondary)
+                    .on_press(Message::DismissNotice { id, idx }),
+            ]
+            .spacing(5)
+            .align_y(Center)
+            .pipe(Element::from)
+        });
+
+        widget::Column::with_children(running.chain(notices))
+            .spacing(2)
+            .into()
+    }
+
+    /// Banner shown if commands of window are restricted.
+    fn restrictions(id: window::Id, window: &Window) -> Option<Element<'_, Message>> {
+        let Ok(content) = &window.content else {
+            return None;
+        };
+        let untrusted = content.untrusted();
+        let blocked = content.blocked();
+        if !window.no_exec && untrusted.is_empty() && blocked.is_empty() {
+            return None;
+        }
+
+        widget::Column::new()
+            .spacing(2)
+            .push(window.no_exec.then(|| {
+                widget::text("View only, lines are not executed")
+                    .style(widget::text::warning)
+                    .size(12)
+            }))
+            .push((!untrusted.is_empty()).then(|| {
+                widget::row![
+                    widget::text(format!(
+                        "Commands of untrusted files are ignored: {}",
+                        untrusted
+                            .iter()
+                            .map(|path| path.display().to_string())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                    .style(widget::text::warning)
+                    .size(12)
+                    .width(Fill),
+                    widget::button(widget::text("Trust files").size(12))
+                        .padding([0, 5])
+                        .on_press(Message::Trust {
+                            id,
+                            directories: false,
+                        }),
+                    widget::button(widget::text("Trust directories").size(12))
+                        .padding([0, 5])
+                        .on_press(Message::Trust {
+                            id,
+                            directories: true,
+                        }),
+                ]
+                .spacing(5)
+                .align_y(Center)
+            }))
+            .push((!blocked.is_empty()).then(|| {
+                widget::text(format!(
+                    "Commands of programs not on allowlist, or setting PATH or LD_* variables, are blocked: {}",
+                    blocked
+                        .iter()
+                        .map(|path| path.to_string_lossy())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+                .style(widget::text::warning)
+                .size(12)
+            }))
+            .pipe(Element::from)
+            .pipe(Some)
+    }
+
+    /// Actions for selected lines.
+    fn selection(id: window::Id, count: usize) -> Element<'static, Message> {
+        widget::row![
+            widget::text(format!("{count} selected"))
+                .size(12)
+                .width(Fill),
+            widget::button(widget::text("Execute selected").size(12))
+                .padding([0, 5])
+                .on_press(Message::ExecSelected(Some(id))),
+            widget::button(widget::text("Clear").size(12))
+                .padding([0, 5])
+                .style(widget::button::secondary)
+                .on_press(Message::ClearSelection(id)),
+        ]
+        .spacing(5)
+        .align_y(Center)
+        .into()
+    }
+
+    /// Recent executions, newest first.
+    fn history(&self, id: window::Id) -> Element<'_, Message> {
+        /// Max amount of entries shown.
+        const SHOWN: usize = 50;
+
+        // windows viewed without execution do not execute history either
+        let no_exec = self.windows.get(&id).is_none_or(|window| window.no_exec);
+
+        let entries = self
+            .history
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .enumerate()
+            .rev()
+            .take(SHOWN)
+            .map(|(idx, entry)| {
+                let status = widget::text(entry.status.to_string()).size(12);
+                widget::row![
+                    widget::text(::humantime::format_rfc3339_seconds(entry.time()).to_string())
+                        .size(12),
+                    if entry.status.is_success() {
+                        status
+                    } else {
+                        status.style(widget::text::danger)
+                    },
+                    widget::text(entry.command.command_line())
+                        .wrapping(widget::text::Wrapping::None)
+                        .font(Font::MONOSPACE)
+                        .size(12)
+                        .width(Fill),
+                    widget::button(widget::text("Jump").size(12))
+                        .padding([0, 5])
+                        .style(widget::button::secondary)
+                        .on_press_maybe(
+                            entry
+                                .source
+                                .is_some()
+                                .then_some(Message::JumpTo { id, idx })
+                        ),
+                    widget::button(widget::text("Run again").size(12))
+                        .padding([0, 5])
+                        .style(widget::button::secondary)
+                        .on_press_maybe((!no_exec).then_some(Message::Rerun { id, idx })),
+                ]
+                .spacing(5)
+                .align_y(Center)
+                .pipe(Element::from)
+            });
+
+        widget::column![
+            widget::text("History")
+                .font(Font {
+                    weight: font::Weight::Bold,
+                    ..Default::default()
+                })
+                .size(12),
+            widget::Column::with_children(entries)
+                .spacing(2)
+                .width(Fill)
+                .pipe(widget::scrollable)
+                .height(150),
+        ]
+        .spacing(5)
+        .pipe(widget::container)
+        .padding(5)
+        .style(widget::container::bordered_box)
+        .into()
+    }
+
+    /// Captured output of a command.
+    fn output_pane(id: window::Id, output: &OutputPane) -> Element<'_, Message> {
+        let header = widget::row![
+            widget::text(&output.description)
+                .wrapping(widget::text::Wrapping::None)
+                .font(Font {
+                    weight: font::Weight::Bold,
+                    ..Default::default()
+                })
+                .size(12)
+                .width(Fill),
+            widget::text(output.status.as_deref().unwrap_or("running")).size(12),
+            widget::button(widget::text("Copy").size(12))
+                .padding([0, 5])
+                .style(widget::button::secondary)
+                .on_press(Message::CopyOutput(id)),
+            widget::button(widget::text("Close").size(12))
+                .padding([0, 5])
+                .style(widget::button::secondary)
+                .on_press(Message::CloseOutput(id)),
+        ]
+        .spacing(5)
+        .align_y(Center);
+
+        let lines = output
+            .lines
+            .iter()
+            .map(|line| {
+                widget::text(line)
+                    .wrapping(widget::text::Wrapping::None)
+                    .font(Font::MONOSPACE)
+                    .size(12)
+                    .pipe(Element::from)
+            })
+            .pipe(widget::Column::with_children)
+            .width(Fill)
+            .pipe(widget::scrollable)
+            .anchor_bottom()
+            .height(200);
+
+        widget::column![header, lines]
+            .spacing(5)
+            .pipe(widget::container)
+            .padding(5)
+            .style(widget::container::bordered_box)
+            .into()
+    }
+
+    /// Scrollable list of visible lines, where only rows in view are
+    /// created.
+    fn line_list<'this>(
+        &self,
+        id: window::Id,
+        window: &'this WindowState,
+        line_view: &'this LineView,
+    ) -> Element<'this, Message> {
+        let WindowState {
+            visible,
+            row_ends,
+            scroll_offset,
+            height,
+            scroll_id,
+            ..
+        } = window;
+        let font_size = self.config.font_size;
+
+        // rows outside of the viewport, give or take some overscan, are
+        // replaced by space of the same height
+        let last = row_ends
+            .partition_point(|end| *end < scroll_offset + height)
+            .saturating_add(OVERSCAN + 1)
+            .min(visible.len());
+        let first = row_ends
+            .partition_point(|end| end < scroll_offset)
+            .saturating_sub(OVERSCAN)
+            .min(last);
+        let end_of = |row: usize| {
+            row.checked_sub(1)
+                .and_then(|row| row_ends.get(row))
+                .copied()
+                .unwrap_or_default()
+        };
+        let above = end_of(first);
+        let below = end_of(visible.len()) - end_of(last);
+
+        visible[first..last]
+            .iter()
+            .map(|&idx| {
+                let line = &line_view[idx];
+                let row = self
+                    .line_row(id, idx, line, window)
+                    .pipe(widget::container)
+                    .height(row_height(line, font_size));
+                if window.cursor == Some(idx) {
+                    row.style(cursor).into()
+                } else if window.highlighted.contains(&idx) {
+                    row.style(highlight).into()
+                } else {
+                    row.into()
+                }
+            })
+            .fold(
+                widget::Column::new().push(widget::space().height(ROW_SPACING + above)),
+                widget::Column::push,
+            )
+            .push(widget::space().height(below))
+            .padding(Padding {
+                left: 5.0,
+                right: 5.0,
+                ..Padding::new(0.0)
+            })
+            .width(Fill)
+            .pipe(widget::scrollable)
+            .id(scroll_id.clone())
+            .on_scroll(move |viewport| Message::Scrolled {
+                id,
+                offset: viewport.absolute_offset().y,
+                height: viewport.bounds().height,
+            })
+            .into()
+    }
+
+    /// Row of line list showing line at idx.
+    fn line_row<'this>(
+        &self,
+        id: window::Id,
+        idx: usize,
+        line: &'this Line<Arc<Cmd>>,
+        window: &WindowState,
+    ) -> Element<'this, Message> {
+        let modifiers = self.modifiers;
+        let font_size = self.config.font_size;
+
+        if line.is_title() {
+            title(
+                line.text(),
+                window.is_collapsed(idx),
+                id,
+                idx,
+                modifiers,
+                font_size + font_size / 3,
+            )
+        } else if line.text().is_empty() {
+            widget::space().height(5).into()
+        } else if line.is_warning() {
+            widget::text(line.text())
+                .wrapping(widget::text::Wrapping::None)
+                .style(widget::text::warning)
+                .size(font_size)
+                .into()
+        } else {
+            if Some(idx) == window.hovered {
+                widget::text(line.text())
+                    .wrapping(widget::text::Wrapping::None)
+                    .size(font_size)
+                    .font(Font {
+                        weight: font::Weight::Bold,
+                        ..Default::default()
+                    })
+            } else {
+                widget::text(line.text())
+                    .wrapping(widget::text::Wrapping::None)
+                    .size(font_size)
+            }
+            .pipe(widget::button)
+            .on_press(if modifiers.control() || modifiers.shift() {
+                Message::SelectLine {
+                    id,
+                    idx,
+                    range: modifiers.shift(),
+                }
+            } else {
+                Message::ExecLine { id, line: idx }
+            })
+            .padding(0)
+            .style(if window.selected.contains(&idx) {
+                widget::button::secondary
+            } else {
+                widget::button::text
+            })
+            .pipe(widget::mouse_area)
+            .on_enter(Message::LineHover { id, idx })
+            .on_exit(Message::LineUnhover { id, idx })
+            .into()
+        }
+    }
+
     /// View ui.
-    pub fn view<'this>(&'this self, id: window::Id) -> impl Into<Element<'this, Message>> {
-        let Some(WindowState {
+    pub fn view<'this>(&'this self, id: window::Id) -> Element<'this, Message> {
+        let Some(state) = self.windows.get(&id) else {
+            return widget::container(widget::space().width(Fill).height(Fill)).into();
+        };
+        let WindowState {
             window,
             hovered,
-            metadata,
             use_filter,
-            filter_re,
             filter,
             container_id,
             filter_id,
+            notices,
+            output,
+            confirm,
+            show_history,
+            selected,
+            loading,
             ..
-        }) = self.windows.get(&id)
-        else {
-            return widget::container(widget::space().width(Fill).height(Fill));
-        };
+        } = state;
         let Window { content, .. } = window.as_ref();
+        let font_size = self.config.font_size;
 
         let line_view = match content {
             Ok(line_view) => line_view,
@@ -889,7 +2714,8 @@ impl State {
                     .padding(5)
                     .style(widget::container::bordered_box)
                     .pipe(widget::container)
-                    .center(Fill);
+                    .center(Fill)
+                    .into();
             }
         };
 
@@ -902,8 +2728,14 @@ impl State {
                         weight: font::Weight::Bold,
                         ..Default::default()
                     })
-                    .size(18),
+                    .size(font_size + font_size / 2),
             )
+            .push(loading.as_ref().map(|loading| {
+                widget::text(format!("Loading, {} lines read...", loading.lines))
+                    .style(widget::text::secondary)
+                    .size(font_size)
+            }))
+            .push(Self::restrictions(id, window))
             .pipe(|col| {
                 if *use_filter {
                     col.push(
@@ -917,83 +2749,36 @@ impl State {
                 }
             })
             .push(
-                line_view
-                    .iter()
-                    .enumerate()
-                    .filter_map({
-                        let mut is_collapsed = false;
-                        move |(idx, line)| {
-                            if line.is_title() {
-                                is_collapsed = metadata
-                                    .get(&Section::Title(Cow::Borrowed(line.text())))
-                                    .is_some_and(|meta| meta.is_collapsed);
-
-                                return Some(title(line.text(), is_collapsed, id));
-                            }
-                            if is_collapsed {
-                                return None;
-                            }
-
-                            if *use_filter && !filter_re.matches(line.text()).matched_all() {
-                                return None;
-                            }
-
-                            if line.text().is_empty() {
-                                widget::space().height(5).pipe(Element::from).pipe(Some)
-                            } else if line.is_warning() {
-                                widget::text(line.text())
-                                    .wrapping(widget::text::Wrapping::None)
-                                    .style(widget::text::warning)
-                                    .size(12)
-                                    .pipe(Element::from)
-                                    .pipe(Some)
-                            } else {
-                                if Some(idx) == *hovered {
-                                    widget::text(line.text())
-                                        .wrapping(widget::text::Wrapping::None)
-                                        .size(12)
-                                        .font(Font {
-                                            weight: font::Weight::Bold,
-                                            ..Default::default()
-                                        })
-                                } else {
-                                    widget::text(line.text())
-                                        .wrapping(widget::text::Wrapping::None)
-                                        .size(12)
-                                }
-                                .pipe(widget::button)
-                                .on_press_maybe(
-                                    (!line.text().is_empty())
-                                        .then_some(Message::ExecLine { id, line: idx }),
-                                )
-                                .padding(0)
-                                .style(widget::button::text)
-                                .pipe(widget::mouse_area)
-                                .on_enter(Message::LineHover { id, idx })
-                                .on_exit(Message::LineUnhover { id, idx })
-                                .pipe(Element::from)
-                                .pipe(Some)
-                            }
-                        }
-                    })
-                    .fold(
-                        widget::Column::new().push(widget::space().height(2)),
-                        widget::Column::push,
-                    )
-                    .padding(Padding {
-                        left: 5.0,
-                        right: 5.0,
-                        ..Padding::new(0.0)
-                    })
-                    .width(Fill)
-                    .spacing(2)
-                    .pipe(widget::scrollable)
+                self.line_list(id, state, line_view)
                     .pipe(widget::container)
                     .height(Fill)
                     .style(widget::container::bordered_box),
             )
+            .push(
+                hovered
+                    .and_then(|idx| line_view.get(idx))
+                    .and_then(|line| line.invocation())
+                    .map(|invocation| {
+                        widget::text(format!("$ {invocation}"))
+                            .wrapping(widget::text::Wrapping::None)
+                            .font(Font::MONOSPACE)
+                            .size(11)
+                    }),
+            )
+            .push(output.as_ref().map(|output| Self::output_pane(id, output)))
+            .push(show_history.then(|| self.history(id)))
+            .push((!selected.is_empty()).then(|| Self::selection(id, selected.len())))
+            .push(self.status(id, notices))
             .pipe(widget::container)
             .id(container_id.clone())
             .padding(5)
+            .pipe(|content| match confirm {
+                Some(confirm) => modal(
+                    content,
+                    confirm_dialog(id, confirm),
+                    Message::CancelExec(id),
+                ),
+                None => content.into(),
+            })
     }
 }
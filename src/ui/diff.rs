@@ -0,0 +1,83 @@
+//! Matching of lines between content before and after a reload.
+
+use ::std::{collections::VecDeque, sync::Arc};
+
+use ::hashbrown::HashMap;
+
+use crate::line_view::{Cmd, Line, LineView};
+
+/// What a line is compared by, lines with equal keys are the same line.
+type Key<'a> = (bool, bool, &'a str);
+
+/// Get key of line.
+fn key(line: &Line<Arc<Cmd>>) -> Key<'_> {
+    (line.is_title(), line.is_warning(), line.text())
+}
+
+/// Lines of new content matched to lines of old content.
+#[derive(Debug, Clone, Default)]
+pub struct Diff {
+    /// For each line of old content, the matching line of new content.
+    new_of: Vec<Option<usize>>,
+    /// Lines of new content that were added or changed.
+    changed: Vec<usize>,
+}
+
+impl Diff {
+    /// Match lines of new to lines of old.
+    ///
+    /// Lines at the start and end which are the same in both are matched in
+    /// place, of the lines between, every line is matched to the first not
+    /// yet matched line of old that is the same.
+    pub fn new(old: &LineView, new: &LineView) -> Self {
+        let prefix = old
+            .iter()
+            .zip(new.iter())
+            .take_while(|(old, new)| key(old) == key(new))
+            .count();
+        let suffix = old
+            .iter()
+            .skip(prefix)
+            .rev()
+            .zip(new.iter().skip(prefix).rev())
+            .take_while(|(old, new)| key(old) == key(new))
+            .count();
+        let old_end = old.len() - suffix;
+        let new_end = new.len() - suffix;
+
+        let mut new_of = vec![None; old.len()];
+        for (idx, new_idx) in new_of.iter_mut().enumerate().take(prefix) {
+            *new_idx = Some(idx);
+        }
+        for (idx, new_idx) in new_of.iter_mut().enumerate().skip(old_end) {
+            *new_idx = Some(idx - old_end + new_end);
+        }
+
+        let mut unmatched = HashMap::<Key, VecDeque<usize>>::new();
+        for idx in prefix..old_end {
+            unmatched.entry(key(&old[idx])).or_default().push_back(idx);
+        }
+
+        let mut changed = Vec::new();
+        for idx in prefix..new_end {
+            let line = &new[idx];
+            if let Some(old_idx) = unmatched.get_mut(&key(line)).and_then(VecDeque::pop_front) {
+                new_of[old_idx] = Some(idx);
+            } else if !line.text().is_empty() {
+                changed.push(idx);
+            }
+        }
+
+        Self { new_of, changed }
+    }
+
+    /// Line of new content matching line at idx of old content, if any.
+    pub fn new_of(&self, idx: usize) -> Option<usize> {
+        self.new_of.get(idx).copied().flatten()
+    }
+
+    /// Lines of new content that were added or changed, in order.
+    pub fn changed(&self) -> &[usize] {
+        &self.changed
+    }
+}
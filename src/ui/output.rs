@@ -0,0 +1,119 @@
+//! Captured output of commands.
+
+use ::std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Read},
+};
+
+use crate::ui::supervisor::ProcessId;
+
+/// Max amount of lines kept by an output pane.
+const SCROLLBACK: usize = 5000;
+
+/// Output of a captured process shown in a window.
+#[derive(Debug, Clone)]
+pub struct OutputPane {
+    /// Process output is captured from.
+    pub process: ProcessId,
+    /// Description of process.
+    pub description: String,
+    /// Captured lines.
+    pub lines: VecDeque<String>,
+    /// Exit status once process has exited.
+    pub status: Option<String>,
+}
+
+impl OutputPane {
+    /// Create a new empty pane for process.
+    pub const fn new(process: ProcessId, description: String) -> Self {
+        Self {
+            process,
+            description,
+            lines: VecDeque::new(),
+            status: None,
+        }
+    }
+
+    /// Push a line, removing the oldest if scrollback is exceeded.
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() >= SCROLLBACK {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// Get contents as a single string.
+    pub fn contents(&self) -> String {
+        let mut contents = String::new();
+        for line in &self.lines {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+        contents
+    }
+}
+
+/// Read lines from a pipe on a new thread, sending them with ansi escapes
+/// removed until the pipe is closed.
+pub fn forward(pipe: impl 'static + Send + Read, tx: ::flume::Sender<String>) {
+    ::std::thread::spawn(move || {
+        let mut pipe = BufReader::new(pipe);
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            match pipe.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(..) => {
+                    let line = String::from_utf8_lossy(&buf);
+                    let line = line.trim_end_matches(['\n', '\r']);
+                    if tx.send(strip_ansi(line)).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => {
+                    ::log::warn!("could not read captured output\n{err}");
+                    break;
+                }
+            }
+        }
+    });
+}
+
+/// Remove ansi escape sequences from text.
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // control sequence, ends with a byte in the range @ to ~
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // operating system command, ends with bell or string terminator
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.next_if_eq(&'\\').is_some() {
+                        break;
+                    }
+                }
+            }
+            // other escapes are two characters long
+            _ => {}
+        }
+    }
+
+    stripped
+}
@@ -0,0 +1,429 @@
+//! Supervision of processes spawned by executing lines.
+
+use ::core::{fmt::Display, time::Duration};
+use ::std::{
+    collections::{BTreeMap, VecDeque},
+    process::{Child, ExitStatus},
+    sync::Arc,
+    time::Instant,
+};
+
+use ::iced::window;
+
+use crate::{
+    history,
+    line_view::{Cmd, CmdId, Invocation, Line},
+    ui::output,
+};
+
+/// Id of a supervised process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProcessId(u64);
+
+/// A running process.
+#[derive(Debug)]
+struct Process {
+    /// Handle to child.
+    child: Child,
+    /// Window process was executed from.
+    window: window::Id,
+    /// Identity of command process was spawned by, not set for reruns.
+    cmd_id: Option<CmdId>,
+    /// Description of process.
+    description: String,
+    /// Point in time after which the process is killed, and the timeout
+    /// used to compute it.
+    deadline: Option<(Instant, Duration)>,
+    /// Set if the process has been killed, and why.
+    killed: Option<ExitKind>,
+    /// History entry of execution.
+    entry: history::Entry,
+}
+
+/// Output captured from a process.
+#[derive(Debug)]
+struct Capture {
+    /// Window process was executed from.
+    window: window::Id,
+    /// Receiver of captured lines, disconnected once both stdout
+    /// and stderr have been closed.
+    rx: ::flume::Receiver<String>,
+}
+
+/// Lines executed by a single process.
+#[derive(Debug, Clone)]
+pub struct Batch {
+    /// Command lines are executed with.
+    pub cmd: Arc<Cmd>,
+    /// Identity of command, batches without one are never limited.
+    pub cmd_id: Option<CmdId>,
+    /// Lines to execute.
+    pub lines: Vec<Line<Arc<Cmd>>>,
+}
+
+impl Batch {
+    /// Batch executing a single line with its own command.
+    pub fn single(line: Line<Arc<Cmd>>) -> Self {
+        Self {
+            cmd: Arc::clone(line.cmd()),
+            cmd_id: line.cmd_id(),
+            lines: vec![line],
+        }
+    }
+
+    /// Resolve invocation of batch.
+    pub fn invocation(&self) -> Option<Invocation> {
+        self.cmd.batch_invocation(&self.lines)
+    }
+}
+
+/// A batch waiting for its command to be below its concurrency limit.
+#[derive(Debug)]
+struct Pending {
+    /// Window batch was executed from.
+    window: window::Id,
+    /// Batch to execute.
+    batch: Batch,
+}
+
+/// How a supervised process ended.
+#[derive(Debug, Clone)]
+pub enum ExitKind {
+    /// Process exited by itself.
+    Exited(ExitStatus),
+    /// Process was killed after running for too long.
+    TimedOut(Duration),
+    /// Process was killed by user.
+    Killed,
+    /// Process could not be spawned or waited on.
+    Failed(String),
+}
+
+/// A process that has finished.
+#[derive(Debug, Clone)]
+pub struct Exit {
+    /// Process that exited.
+    pub process: ProcessId,
+    /// Window process was executed from.
+    pub window: window::Id,
+    /// Description of process.
+    pub description: String,
+    /// How the process ended.
+    pub kind: ExitKind,
+    /// History entry of execution, with status set.
+    pub entry: history::Entry,
+}
+
+impl Exit {
+    /// Create a new exit, setting the status of entry.
+    fn new(
+        process: ProcessId,
+        window: window::Id,
+        description: String,
+        kind: ExitKind,
+        mut entry: history::Entry,
+    ) -> Self {
+        entry.status = match &kind {
+            ExitKind::Exited(status) => status
+                .code()
+                .map_or(history::Status::Signaled, history::Status::Exited),
+            ExitKind::TimedOut(..) => history::Status::TimedOut,
+            ExitKind::Killed => history::Status::Killed,
+            ExitKind::Failed(err) => history::Status::Failed(err.clone()),
+        };
+        Self {
+            process,
+            window,
+            description,
+            kind,
+            entry,
+        }
+    }
+
+    /// Check if exit should be reported to user.
+    pub fn is_failure(&self) -> bool {
+        match &self.kind {
+            ExitKind::Exited(status) => !status.success(),
+            ExitKind::TimedOut(..) | ExitKind::Failed(..) => true,
+            ExitKind::Killed => false,
+        }
+    }
+}
+
+impl Display for Exit {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        let Self {
+            description, kind, ..
+        } = self;
+        match kind {
+            ExitKind::Exited(status) => write!(f, "{description}: {status}"),
+            ExitKind::TimedOut(timeout) => {
+                write!(f, "{description}: killed after {}s", timeout.as_secs_f64())
+            }
+            ExitKind::Killed => write!(f, "{description}: killed"),
+            ExitKind::Failed(err) => write!(f, "{description}: {err}"),
+        }
+    }
+}
+
+/// Something that happened to a supervised process.
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// Process with captured output was started.
+    Captured {
+        /// Process output is captured from.
+        process: ProcessId,
+        /// Window process was executed from.
+        window: window::Id,
+        /// Description of process.
+        description: String,
+    },
+    /// Process wrote a line of output.
+    Output {
+        /// Process output is captured from.
+        process: ProcessId,
+        /// Window process was executed from.
+        window: window::Id,
+        /// Line of output.
+        line: String,
+    },
+    /// Process exited.
+    Exit(Exit),
+}
+
+/// Keeps track of spawned processes, reaping them when they exit.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    /// Id to use for next process.
+    next_id: u64,
+    /// Currently running processes.
+    running: BTreeMap<ProcessId, Process>,
+    /// Lines waiting to be executed.
+    pending: VecDeque<Pending>,
+    /// Output being captured, may outlive process.
+    captures: BTreeMap<ProcessId, Capture>,
+    /// Events not yet reported.
+    events: Vec<Event>,
+}
+
+impl Supervisor {
+    /// Check if there are no running or pending processes, nor any
+    /// output or events left to report.
+    pub fn is_idle(&self) -> bool {
+        self.running.is_empty()
+            && self.pending.is_empty()
+            && self.captures.is_empty()
+            && self.events.is_empty()
+    }
+
+    /// Check if command of batch may spawn another process.
+    fn below_limit(&self, batch: &Batch) -> bool {
+        let (Some(limit), Some(cmd_id)) = (batch.cmd.get_limit(), batch.cmd_id) else {
+            return true;
+        };
+        self.running
+            .values()
+            .filter(|process| process.cmd_id == Some(cmd_id))
+            .count()
+            < limit.get()
+    }
+
+    /// Group lines by how they are executed, lines sharing a batch command
+    /// are grouped together and all other lines are executed on their own.
+    /// Order of first occurrence is kept.
+    pub fn batches(lines: impl IntoIterator<Item = Line<Arc<Cmd>>>) -> Vec<Batch> {
+        let mut batches = Vec::<Batch>::new();
+        for line in lines {
+            if line.cmd().is_batch()
+                && let Some(cmd_id) = line.cmd_id()
+                && let Some(batch) = batches
+                    .iter_mut()
+                    .find(|batch| batch.cmd_id == Some(cmd_id))
+            {
+                batch.lines.push(line);
+            } else {
+                batches.push(Batch::single(line));
+            }
+        }
+        batches
+    }
+
+    /// Execute a line, if the command of the line has reached its limit
+    /// of running processes the line is queued.
+    /// Failure to spawn is reported as an exit.
+    pub fn execute(&mut self, window: window::Id, line: Line<Arc<Cmd>>) {
+        self.execute_batch(window, Batch::single(line));
+    }
+
+    /// Execute a batch using a single process, queued like [Supervisor::execute].
+    pub fn execute_batch(&mut self, window: window::Id, batch: Batch) {
+        if self.below_limit(&batch) {
+            self.spawn(window, &batch);
+        } else {
+            self.pending.push_back(Pending { window, batch });
+        }
+    }
+
+    /// Execute a resolved history entry again, limits and timeouts of the
+    /// command that originally spawned it do not apply.
+    pub fn rerun(&mut self, window: window::Id, entry: &history::Entry) {
+        if let Some(invocation) = entry.invocation.clone() {
+            self.spawn_invocation(window, invocation, entry.renew(), None);
+        }
+    }
+
+    /// Spawn process for batch.
+    fn spawn(&mut self, window: window::Id, batch: &Batch) {
+        if let Some(line) = batch.lines.first()
+            && let Some(invocation) = batch.invocation()
+        {
+            let entry = history::Entry::new(line, &invocation, batch.cmd.get_confirm());
+            self.spawn_invocation(window, invocation, entry, Some(batch));
+        }
+    }
+
+    /// Spawn process for invocation.
+    fn spawn_invocation(
+        &mut self,
+        window: window::Id,
+        invocation: Invocation,
+        entry: history::Entry,
+        batch: Option<&Batch>,
+    ) {
+        let id = ProcessId(self.next_id);
+        self.next_id += 1;
+        let description = invocation.command_line();
+        let is_captured = invocation.capture;
+
+        let mut child = match invocation.spawn() {
+            Ok(child) => child,
+            Err(err) => {
+                self.events.push(Event::Exit(Exit::new(
+                    id,
+                    window,
+                    description,
+                    ExitKind::Failed(err.to_string()),
+                    entry,
+                )));
+                return;
+            }
+        };
+
+        if is_captured {
+            let (tx, rx) = ::flume::unbounded();
+            if let Some(stdout) = child.stdout.take() {
+                output::forward(stdout, tx.clone());
+            }
+            if let Some(stderr) = child.stderr.take() {
+                output::forward(stderr, tx);
+            }
+            self.captures.insert(id, Capture { window, rx });
+            self.events.push(Event::Captured {
+                process: id,
+                window,
+                description: description.clone(),
+            });
+        }
+
+        self.running.insert(
+            id,
+            Process {
+                child,
+                window,
+                deadline: batch
+                    .and_then(|batch| batch.cmd.get_timeout())
+                    .map(|timeout| (Instant::now() + timeout, timeout)),
+                cmd_id: batch.and_then(|batch| batch.cmd_id),
+                description,
+                killed: None,
+                entry,
+            },
+        );
+    }
+
+    /// Kill a running process.
+    pub fn kill(&mut self, id: ProcessId) {
+        if let Some(process) = self.running.get_mut(&id) {
+            if let Err(err) = process.child.kill() {
+                ::log::warn!("could not kill {}\n{err}", process.description);
+            }
+            process.killed.get_or_insert(ExitKind::Killed);
+        }
+    }
+
+    /// Running processes executed from window.
+    pub fn running(&self, window: window::Id) -> impl Iterator<Item = (ProcessId, &str)> {
+        self.running
+            .iter()
+            .filter(move |(_, process)| process.window == window)
+            .map(|(id, process)| (*id, process.description.as_str()))
+    }
+
+    /// Reap exited processes, kill processes that have passed their deadline,
+    /// collect captured output and spawn pending lines that are allowed to run.
+    pub fn reap(&mut self) -> Vec<Event> {
+        let now = Instant::now();
+        let mut events = ::core::mem::take(&mut self.events);
+
+        // output is collected before exits such that it is reported first
+        self.captures.retain(|process, capture| {
+            events.extend(capture.rx.try_iter().map(|line| Event::Output {
+                process: *process,
+                window: capture.window,
+                line,
+            }));
+            !capture.rx.is_disconnected()
+        });
+
+        let mut exited = Vec::new();
+        for (id, process) in &mut self.running {
+            if process.killed.is_none()
+                && let Some((deadline, timeout)) = process.deadline
+                && deadline <= now
+            {
+                if let Err(err) = process.child.kill() {
+                    ::log::warn!("could not kill {}\n{err}", process.description);
+                }
+                process.killed = Some(ExitKind::TimedOut(timeout));
+            }
+
+            match process.child.try_wait() {
+                Ok(None) => {}
+                Ok(Some(status)) => exited.push((*id, ExitKind::Exited(status))),
+                Err(err) => exited.push((*id, ExitKind::Failed(err.to_string()))),
+            }
+        }
+
+        for (id, kind) in exited {
+            if let Some(Process {
+                window,
+                description,
+                killed,
+                entry,
+                ..
+            }) = self.running.remove(&id)
+            {
+                events.push(Event::Exit(Exit::new(
+                    id,
+                    window,
+                    description,
+                    killed.unwrap_or(kind),
+                    entry,
+                )));
+            }
+        }
+
+        let pending = ::core::mem::take(&mut self.pending);
+        for Pending { window, batch } in pending {
+            if self.below_limit(&batch) {
+                self.spawn(window, &batch);
+            } else {
+                self.pending.push_back(Pending { window, batch });
+            }
+        }
+
+        // spawn failures of pending lines
+        events.append(&mut self.events);
+        events
+    }
+}